@@ -0,0 +1,142 @@
+//! Actions sent to the module through Waybar's `actions` bindings
+
+use crate::errors::VirtualDesktopError;
+
+/// Verbs understood by `do_action`
+const VERBS: &str = "<id>, next, prev, last, next-populated, prev-populated, goto:<name>, \
+                     move-window:<id>, reset, resync, dump-metrics";
+
+/// Operation requested through `do_action`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Switch to a desktop by ID, given as a bare number
+    Switch(u32),
+    /// Switch to the next desktop with the plugin's `nextdesk`
+    Next,
+    /// Switch to the previous desktop with the plugin's `prevdesk`
+    Prev,
+    /// Switch back with the plugin's `lastdesk`, using its focus history
+    Last,
+    /// Switch to the next desktop that has windows with `cyclevdesks`
+    NextPopulated,
+    /// Switch to the previous desktop that has windows with `backcyclevdesks`
+    PrevPopulated,
+    /// Switch to a desktop by exact, case-insensitive or prefix match of its name
+    Goto(String),
+    /// Move the active window to a desktop by ID
+    MoveWindow(u32),
+    /// Reset the plugin's desktops
+    Reset,
+    /// Fetch the full state from Hyprland now
+    Resync,
+    /// Log the performance metrics
+    DumpMetrics,
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Switch(vdesk_id) => write!(f, "{}", vdesk_id),
+            Self::Next => write!(f, "next"),
+            Self::Prev => write!(f, "prev"),
+            Self::Last => write!(f, "last"),
+            Self::NextPopulated => write!(f, "next-populated"),
+            Self::PrevPopulated => write!(f, "prev-populated"),
+            Self::Goto(name) => write!(f, "goto:{}", name),
+            Self::MoveWindow(vdesk_id) => write!(f, "move-window:{}", vdesk_id),
+            Self::Reset => write!(f, "reset"),
+            Self::Resync => write!(f, "resync"),
+            Self::DumpMetrics => write!(f, "dump-metrics"),
+        }
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = VirtualDesktopError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let action = s.trim();
+        if let Ok(vdesk_id) = action.parse::<u32>() {
+            return Ok(Self::Switch(vdesk_id));
+        }
+
+        let (verb, argument) = match action.split_once(':') {
+            Some((verb, argument)) => (verb, Some(argument.trim())),
+            None => (action, None),
+        };
+
+        match (verb, argument) {
+            ("goto", Some(name)) if !name.is_empty() => Ok(Self::Goto(name.to_string())),
+            ("goto", _) => Err(VirtualDesktopError::invalid_action(s, "goto needs a desktop name, e.g. goto:Focus")),
+            ("move-window", argument) => argument
+                .and_then(|id| id.parse().ok())
+                .map(Self::MoveWindow)
+                .ok_or_else(|| VirtualDesktopError::invalid_action(s, "move-window needs a desktop id, e.g. move-window:2")),
+            (verb, None) => match verb {
+                "next" => Ok(Self::Next),
+                "prev" => Ok(Self::Prev),
+                "last" => Ok(Self::Last),
+                "next-populated" => Ok(Self::NextPopulated),
+                "prev-populated" => Ok(Self::PrevPopulated),
+                "reset" => Ok(Self::Reset),
+                "resync" => Ok(Self::Resync),
+                "dump-metrics" => Ok(Self::DumpMetrics),
+                _ => Err(VirtualDesktopError::invalid_action(s, &format!("unknown verb, expected one of {}", VERBS))),
+            },
+            (verb, Some(_)) => Err(VirtualDesktopError::invalid_action(
+                s,
+                &format!("'{}' does not take an argument", verb),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_parsing() {
+        assert_eq!("3".parse::<Action>().unwrap(), Action::Switch(3));
+        assert_eq!(" next ".parse::<Action>().unwrap(), Action::Next);
+        assert_eq!("prev-populated".parse::<Action>().unwrap(), Action::PrevPopulated);
+        assert_eq!("goto:Research".parse::<Action>().unwrap(), Action::Goto("Research".to_string()));
+        assert_eq!("goto: Deep Work".parse::<Action>().unwrap(), Action::Goto("Deep Work".to_string()));
+        assert_eq!("move-window:2".parse::<Action>().unwrap(), Action::MoveWindow(2));
+        assert_eq!("dump-metrics".parse::<Action>().unwrap(), Action::DumpMetrics);
+    }
+
+    #[test]
+    fn test_action_round_trips_through_display() {
+        let actions = [
+            Action::Switch(4),
+            Action::Next,
+            Action::Prev,
+            Action::Last,
+            Action::NextPopulated,
+            Action::PrevPopulated,
+            Action::Goto("Comms".to_string()),
+            Action::MoveWindow(1),
+            Action::Reset,
+            Action::Resync,
+            Action::DumpMetrics,
+        ];
+        for action in actions {
+            assert_eq!(action.to_string().parse::<Action>().unwrap(), action);
+        }
+    }
+
+    #[test]
+    fn test_invalid_actions_explain_the_problem() {
+        let error = "jump".parse::<Action>().unwrap_err();
+        assert!(matches!(error, VirtualDesktopError::InvalidAction { .. }));
+        assert!(error.to_string().contains("unknown verb"));
+        assert!(error.to_string().contains("next-populated"));
+
+        assert!("goto:".parse::<Action>().unwrap_err().to_string().contains("desktop name"));
+        assert!("move-window:two".parse::<Action>().unwrap_err().to_string().contains("desktop id"));
+        assert!("move-window".parse::<Action>().is_err());
+        assert!("next:2".parse::<Action>().unwrap_err().to_string().contains("does not take an argument"));
+        assert!("".parse::<Action>().is_err());
+    }
+}
@@ -0,0 +1,269 @@
+//! Ordered queue for Hyprland commands triggered from the bar
+
+use crate::config::ModuleConfig;
+use crate::errors::VirtualDesktopError;
+use crate::hyprland::{is_connection_error, HyprlandIPC};
+use crate::metrics::PerformanceMetrics;
+use crate::vdesk::{VirtualDesktop, VirtualDesktopsManager};
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch, Mutex};
+
+/// Reports the outcome of commands sent from the bar back to the UI thread
+pub type CommandResultSender = mpsc::UnboundedSender<std::result::Result<(), String>>;
+
+/// Hyprland operation triggered from the bar
+#[derive(Debug, Clone, PartialEq)]
+pub enum DesktopCommand {
+    Switch(u32),
+    /// Switch to the desktop a name resolves to in the manager's state
+    SwitchByName(String),
+    MoveActiveWindow(u32),
+    Rename(u32, String),
+    Reset,
+    /// The plugin's `nextdesk`, creating a desktop past the last one
+    NextDesk,
+    /// The plugin's `prevdesk`
+    PrevDesk,
+    /// The plugin's `cyclevdesks` through populated desktops
+    CycleDesks,
+    /// The plugin's `backcyclevdesks` through populated desktops
+    BackCycleDesks,
+    /// The plugin's `lastdesk`, following its own focus history
+    LastDesk,
+}
+
+/// Cheap handle for submitting commands to the shared command worker
+#[derive(Debug, Clone)]
+pub struct CommandQueue {
+    tx: mpsc::UnboundedSender<DesktopCommand>,
+}
+
+impl CommandQueue {
+    /// Create a queue and the receiver its worker consumes
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<DesktopCommand>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx }, rx)
+    }
+
+    /// Create a queue whose worker runs on the module's runtime
+    pub fn spawn(
+        runtime: &tokio::runtime::Handle,
+        config: watch::Receiver<ModuleConfig>,
+        manager: Arc<Mutex<VirtualDesktopsManager>>,
+        metrics: Arc<PerformanceMetrics>,
+        results: CommandResultSender,
+    ) -> Self {
+        let (queue, rx) = Self::new();
+        let worker = CommandWorker::new(config, manager, metrics, results);
+        runtime.spawn(worker.run(rx));
+        queue
+    }
+
+    /// Queue a command without waiting for it to run
+    pub fn submit(&self, command: DesktopCommand) {
+        log::debug!("Queueing {:?}", command);
+        if self.tx.send(command).is_err() {
+            log::warn!("Command worker has stopped; dropping command");
+        }
+    }
+}
+
+/// Runs queued commands one at a time over a single IPC client
+struct CommandWorker {
+    ipc: Option<HyprlandIPC>,
+    /// Latest configuration, used whenever a new client has to be created
+    config: watch::Receiver<ModuleConfig>,
+    /// Desktop state kept current by the monitor, for resolving names
+    manager: Arc<Mutex<VirtualDesktopsManager>>,
+    metrics: Arc<PerformanceMetrics>,
+    results: CommandResultSender,
+}
+
+impl CommandWorker {
+    fn new(
+        config: watch::Receiver<ModuleConfig>,
+        manager: Arc<Mutex<VirtualDesktopsManager>>,
+        metrics: Arc<PerformanceMetrics>,
+        results: CommandResultSender,
+    ) -> Self {
+        Self {
+            ipc: None,
+            config,
+            manager,
+            metrics,
+            results,
+        }
+    }
+
+    async fn run(mut self, mut rx: mpsc::UnboundedReceiver<DesktopCommand>) {
+        while let Some(command) = rx.recv().await {
+            let result = self.execute(&command).await.map_err(|e| {
+                log::error!("Failed to run {:?}: {}", command, e);
+                // Names that resolve to no single desktop never reach Hyprland
+                if !is_name_lookup_error(&e) {
+                    self.metrics.record_ipc_error();
+                }
+                // Rebuild the client on the next command in case Hyprland restarted
+                if is_connection_error(&e) {
+                    self.ipc = None;
+                }
+                e.to_string()
+            });
+            self.metrics.record_command_executed();
+            // The UI may already be gone during shutdown
+            let _ = self.results.send(result);
+        }
+        log::debug!("Command queue closed, stopping worker");
+    }
+
+    /// The IPC client is created on first use and then reused for every command
+    async fn ipc(&mut self) -> anyhow::Result<&HyprlandIPC> {
+        if self.ipc.is_none() {
+            let config = self.config.borrow().clone();
+            let ipc = HyprlandIPC::with_config(&config).await?;
+            self.ipc = Some(ipc);
+        }
+        Ok(self.ipc.as_ref().expect("IPC client initialized above"))
+    }
+
+    async fn execute(&mut self, command: &DesktopCommand) -> anyhow::Result<()> {
+        let manager = Arc::clone(&self.manager);
+        let ipc = self.ipc().await?;
+        match command {
+            DesktopCommand::Switch(vdesk_id) => ipc.switch_to_virtual_desktop(*vdesk_id).await,
+            DesktopCommand::SwitchByName(query) => {
+                let vdesk = manager.lock().await.find_by_name(query)?;
+                log::debug!("'{}' resolved to virtual desktop {} '{}'", query, vdesk.id, vdesk.name);
+                // Numeric names would be read as IDs by the plugin
+                if vdesk.name.trim().parse::<u32>().is_ok() {
+                    ipc.switch_to_virtual_desktop(vdesk.id).await
+                } else {
+                    ipc.switch_to_virtual_desktop_by_name(&vdesk.name).await
+                }
+            }
+            DesktopCommand::MoveActiveWindow(vdesk_id) => ipc.move_to_desk(*vdesk_id).await,
+            DesktopCommand::Rename(vdesk_id, new_name) => {
+                let state = ipc.get_virtual_desktop_state().await?;
+                let vdesks: Vec<VirtualDesktop> = serde_json::from_str(&state)?;
+                let names = plugin_names_value(&vdesks, *vdesk_id, new_name);
+                ipc.set_keyword("plugin:virtual-desktops:names", &names).await
+            }
+            DesktopCommand::Reset => ipc.reset_desks(None).await,
+            DesktopCommand::NextDesk => ipc.next_desk().await,
+            DesktopCommand::PrevDesk => ipc.prev_desk().await,
+            DesktopCommand::CycleDesks => ipc.cycle_desks().await,
+            DesktopCommand::BackCycleDesks => ipc.back_cycle_desks().await,
+            DesktopCommand::LastDesk => ipc.last_desk().await,
+        }
+    }
+}
+
+fn is_name_lookup_error(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<VirtualDesktopError>(),
+        Some(VirtualDesktopError::UnknownDesktop { .. } | VirtualDesktopError::AmbiguousDesktop { .. })
+    )
+}
+
+/// Build the plugin's `names` option value with one desktop renamed
+fn plugin_names_value(vdesks: &[VirtualDesktop], renamed_id: u32, new_name: &str) -> String {
+    let mut names: BTreeMap<u32, &str> = vdesks.iter().map(|vd| (vd.id, vd.name.as_str())).collect();
+    names.insert(renamed_id, new_name);
+    names
+        .iter()
+        .map(|(id, name)| format!("{}:{}", id, name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+
+    fn create_test_vdesk(id: u32, name: &str) -> VirtualDesktop {
+        VirtualDesktop {
+            id,
+            name: name.to_string(),
+            focused: false,
+            populated: true,
+            window_count: 1,
+            workspaces: vec![id],
+            urgent: false,
+        }
+    }
+
+    #[test]
+    fn test_plugin_names_value() {
+        let vdesks = vec![create_test_vdesk(2, "Research"), create_test_vdesk(1, "Focus")];
+
+        assert_eq!(plugin_names_value(&vdesks, 2, "Comms"), "1:Focus, 2:Comms");
+        // Renaming a desktop the plugin has not created yet adds it
+        assert_eq!(plugin_names_value(&vdesks, 3, "Music"), "1:Focus, 2:Research, 3:Music");
+    }
+
+    #[tokio::test]
+    async fn test_worker_runs_commands_in_order_over_shared_client() {
+        let socket_path = std::env::temp_dir().join(format!(
+            "waybar-vd-test-{}-queue.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        // Answer like Hyprland: one request per connection, rejecting the reset
+        let server = tokio::spawn(async move {
+            let mut received = Vec::new();
+            for _ in 0..4 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 256];
+                let n = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let reply = if request.contains("vdeskreset") { "error" } else { "ok" };
+                stream.write_all(reply.as_bytes()).await.unwrap();
+                received.push(request);
+            }
+            received
+        });
+
+        let metrics = Arc::new(PerformanceMetrics::new());
+        let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+        let (_config_tx, config_rx) = watch::channel(ModuleConfig::default());
+        let mut manager = VirtualDesktopsManager::new();
+        manager.set_virtual_desktops(vec![create_test_vdesk(1, "Focus"), create_test_vdesk(2, "Research")]);
+        let manager = Arc::new(Mutex::new(manager));
+        let mut worker = CommandWorker::new(config_rx, manager, Arc::clone(&metrics), results_tx);
+        worker.ipc = Some(HyprlandIPC::with_socket_path(socket_path.clone(), 1000));
+
+        let (queue, rx) = CommandQueue::new();
+        queue.submit(DesktopCommand::Switch(2));
+        queue.submit(DesktopCommand::SwitchByName("res".to_string()));
+        queue.submit(DesktopCommand::SwitchByName("Music".to_string()));
+        queue.submit(DesktopCommand::MoveActiveWindow(3));
+        queue.submit(DesktopCommand::Reset);
+        drop(queue);
+        worker.run(rx).await;
+
+        let received = server.await.unwrap();
+        assert_eq!(
+            received,
+            vec!["dispatch vdesk 2", "dispatch vdesk Research", "dispatch movetodesk 3", "dispatch vdeskreset"]
+        );
+
+        assert_eq!(results_rx.recv().await, Some(Ok(())));
+        assert_eq!(results_rx.recv().await, Some(Ok(())));
+        // Unknown names fail without reaching Hyprland
+        assert!(matches!(results_rx.recv().await, Some(Err(e)) if e.contains("Music")));
+        assert_eq!(results_rx.recv().await, Some(Ok(())));
+        assert!(matches!(results_rx.recv().await, Some(Err(_))));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.commands_executed_total, 5);
+        assert_eq!(snapshot.ipc_errors_total, 1);
+
+        let _ = std::fs::remove_file(&socket_path);
+    }
+}
@@ -1,25 +1,22 @@
 //! Configuration for virtual desktop display and behavior
 
 // src/config.rs
+use crate::vdesk::VirtualDesktop;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Virtual desktop sorting strategy
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum SortStrategy {
+    #[default]
     Number,
     Name,
     #[serde(rename = "focused-first")]
     FocusedFirst,
 }
 
-impl Default for SortStrategy {
-    fn default() -> Self {
-        Self::Number
-    }
-}
-
 impl std::fmt::Display for SortStrategy {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -47,25 +44,314 @@ impl std::str::FromStr for SortStrategy {
     }
 }
 
+/// Animation used when desktop buttons appear or disappear
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum TransitionType {
+    None,
+    #[default]
+    Crossfade,
+    SlideLeft,
+    SlideRight,
+}
+
+impl std::fmt::Display for TransitionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Crossfade => write!(f, "crossfade"),
+            Self::SlideLeft => write!(f, "slide-left"),
+            Self::SlideRight => write!(f, "slide-right"),
+        }
+    }
+}
+
+impl std::str::FromStr for TransitionType {
+    type Err = crate::errors::VirtualDesktopError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "crossfade" => Ok(Self::Crossfade),
+            "slide-left" => Ok(Self::SlideLeft),
+            "slide-right" => Ok(Self::SlideRight),
+            _ => Err(crate::errors::VirtualDesktopError::invalid_config(
+                "transition_type",
+                s,
+                "must be 'none', 'crossfade', 'slide-left', or 'slide-right'"
+            )),
+        }
+    }
+}
+
+/// Action performed when a desktop button is clicked
+///
+/// `exec:` followed by a shell command runs it with `{id}` and `{name}` substituted;
+/// anything else must be a built-in action name. The keys are prefixed with `vdesk-`
+/// because Waybar runs its own `on-click` commands for every module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ClickAction {
+    None,
+    Switch,
+    MoveActiveWindowHere,
+    Rename,
+    Reset,
+    Command(String),
+}
+
+impl std::fmt::Display for ClickAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Switch => write!(f, "switch"),
+            Self::MoveActiveWindowHere => write!(f, "move-active-window-here"),
+            Self::Rename => write!(f, "rename"),
+            Self::Reset => write!(f, "reset"),
+            Self::Command(command) => write!(f, "exec:{}", command),
+        }
+    }
+}
+
+impl TryFrom<String> for ClickAction {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let action = s.trim();
+        if let Some(command) = action.strip_prefix("exec:") {
+            return match command.trim() {
+                "" => Err("exec: needs a shell command, e.g. exec:notify-send {name}".to_string()),
+                command => Ok(Self::Command(command.to_string())),
+            };
+        }
+        match action {
+            "" | "none" => Ok(Self::None),
+            "switch" => Ok(Self::Switch),
+            "move-active-window-here" => Ok(Self::MoveActiveWindowHere),
+            "rename" => Ok(Self::Rename),
+            "reset" => Ok(Self::Reset),
+            _ => Err(format!(
+                "unknown click action '{}', expected none, switch, move-active-window-here, rename, reset or exec:<command>",
+                action
+            )),
+        }
+    }
+}
+
+impl From<ClickAction> for String {
+    fn from(action: ClickAction) -> Self {
+        action.to_string()
+    }
+}
+
+/// Built-in actions are offered for completion; shell commands need the `exec:` prefix
+#[cfg(test)]
+impl schemars::JsonSchema for ClickAction {
+    fn schema_name() -> String {
+        "ClickAction".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        let builtins: Vec<String> = [Self::None, Self::Switch, Self::MoveActiveWindowHere, Self::Rename, Self::Reset]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "description": "Built-in action, or exec: followed by a shell command with {id} and {name} substituted",
+            "anyOf": [
+                { "type": "string", "enum": builtins },
+                { "type": "string", "pattern": "^\\s*exec:" }
+            ]
+        }))
+        .expect("valid schema")
+    }
+}
+
+/// Keys Waybar itself handles for every module, which are passed through to us as well
+const WAYBAR_MODULE_KEYS: &[&str] = &[
+    "module_path", "actions", "tooltip", "min-length", "max-length", "rotate", "align",
+    "justify", "expand", "smooth-scrolling-threshold", "menu", "menu-file", "menu-actions",
+    "on-double-click", "on-triple-click", "on-click-backward", "on-click-forward",
+    "on-scroll-left", "on-scroll-right", "on-update", "on-click", "on-click-middle",
+    "on-click-right", "on-scroll-up", "on-scroll-down",
+];
+
+/// Configuration key that matches no option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    pub key: String,
+    /// Closest known option, if any is near enough to be a likely typo
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.suggestion {
+            Some(suggestion) => write!(f, "'{}' (did you mean '{}'?)", self.key, suggestion),
+            None => write!(f, "'{}'", self.key),
+        }
+    }
+}
+
+/// Canonical snake_case name of every option
+pub fn known_keys() -> Vec<String> {
+    match serde_json::to_value(ModuleConfig::default()) {
+        Ok(serde_json::Value::Object(map)) => map.into_iter().map(|(key, _)| key).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Describe keys no option accepted, suggesting the closest known option for each
+pub fn find_unknown_keys<'a>(keys: impl IntoIterator<Item = &'a str>) -> Vec<UnknownKey> {
+    let known = known_keys();
+    let mut unknown: Vec<UnknownKey> = keys
+        .into_iter()
+        .filter(|key| !WAYBAR_MODULE_KEYS.contains(key))
+        .map(|key| UnknownKey {
+            key: key.to_string(),
+            suggestion: closest_key(key, &known),
+        })
+        .collect();
+    unknown.sort_by(|a, b| a.key.cmp(&b.key));
+    unknown
+}
+
+/// Nearest known key, spelled the way the user spells keys (kebab or snake case)
+fn closest_key(key: &str, known: &[String]) -> Option<String> {
+    let normalized = key.to_lowercase().replace('-', "_");
+    let (distance, best) = known
+        .iter()
+        .map(|candidate| (edit_distance(&normalized, candidate), candidate))
+        .min()?;
+
+    // Allow roughly one typo per four characters
+    if distance > (normalized.len() / 4).max(1) {
+        return None;
+    }
+    Some(if key.contains('-') { best.replace('_', "-") } else { best.clone() })
+}
+
+/// Levenshtein distance between two keys
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 /// Virtual desktop module configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct ModuleConfig {
+    /// Label of each desktop button; supports {name}, {icon}, {id} and {window_count}
     #[serde(default = "default_format")]
     pub format: String,
-    #[serde(default = "default_show_empty")]
+    /// Label of the focused desktop, instead of `format`
+    #[serde(default, alias = "format-focused")]
+    pub format_focused: Option<String>,
+    /// Label of desktops with a window requesting attention, instead of `format`
+    #[serde(default, alias = "format-urgent")]
+    pub format_urgent: Option<String>,
+    /// Label of unfocused desktops that have windows, instead of `format`
+    #[serde(default, alias = "format-populated")]
+    pub format_populated: Option<String>,
+    /// Label of unfocused desktops without windows, instead of `format`
+    #[serde(default, alias = "format-empty")]
+    pub format_empty: Option<String>,
+    /// Placeholder label while Hyprland is unreachable
+    #[serde(default = "default_format_disconnected", alias = "format-disconnected")]
+    pub format_disconnected: String,
+    /// Placeholder label while refreshing the desktops fails
+    #[serde(default = "default_format_error", alias = "format-error")]
+    pub format_error: String,
+    /// Show desktops that have no windows
+    #[serde(default = "default_show_empty", alias = "show-empty")]
     pub show_empty: bool,
+    /// Text placed between desktop buttons
     #[serde(default = "default_separator")]
     pub separator: String,
-    #[serde(default)]
+    /// Icons for {icon}, keyed by desktop name or id
+    #[serde(default, alias = "format-icons")]
     pub format_icons: HashMap<String, String>,
-    #[serde(default = "default_show_window_count")]
+    /// Include the window count in tooltips
+    #[serde(default = "default_show_window_count", alias = "show-window-count")]
     pub show_window_count: bool,
-    #[serde(default)]
+    /// Order of the desktop buttons
+    #[serde(default, alias = "sort-by")]
     pub sort_by: SortStrategy,
-    #[serde(default = "default_retry_max")]
+    /// No longer used; reconnecting is limited by `max_reconnect_attempts`
+    #[serde(default = "default_retry_max", alias = "retry-max")]
     pub retry_max: u32,
-    #[serde(default = "default_retry_base_delay_ms")]
+    /// Initial delay between reconnect attempts, doubled after each failure
+    #[serde(default = "default_retry_base_delay_ms", alias = "retry-base-delay-ms")]
     pub retry_base_delay_ms: u64,
+    /// Window for coalescing bursts of Hyprland events into one refresh
+    #[serde(default = "default_update_debounce_ms", alias = "update-debounce-ms")]
+    pub update_debounce_ms: u64,
+    /// Time allowed for each IPC connect, write and read
+    #[serde(default = "default_ipc_timeout_ms", alias = "ipc-timeout-ms")]
+    pub ipc_timeout_ms: u64,
+    /// Consecutive failed reconnects before monitoring stops; 0 retries forever
+    #[serde(default, alias = "max-reconnect-attempts")]
+    pub max_reconnect_attempts: u32,
+    /// Hyprland instance to connect to instead of HYPRLAND_INSTANCE_SIGNATURE or discovery
+    #[serde(default, alias = "instance-signature")]
+    pub instance_signature: Option<String>,
+    /// Animation used when desktop buttons appear or disappear
+    #[serde(default, alias = "transition-type")]
+    pub transition_type: TransitionType,
+    /// Length of the transition animation
+    #[serde(default = "default_transition_duration_ms", alias = "transition-duration-ms")]
+    pub transition_duration_ms: u32,
+    /// Shell command run on scroll up instead of switching desktops
+    #[serde(default, alias = "vdesk-on-scroll-up")]
+    pub vdesk_on_scroll_up: Option<String>,
+    /// Shell command run on scroll down instead of switching desktops
+    #[serde(default, alias = "vdesk-on-scroll-down")]
+    pub vdesk_on_scroll_down: Option<String>,
+    /// Wrap around from the last desktop to the first when scrolling
+    #[serde(default = "default_scroll_wrap", alias = "scroll-wrap")]
+    pub scroll_wrap: bool,
+    /// Skip desktops without windows when scrolling
+    #[serde(default, alias = "scroll-skip-empty")]
+    pub scroll_skip_empty: bool,
+    /// Accumulated smooth-scroll distance needed for one switch
+    #[serde(default = "default_scroll_threshold", alias = "scroll-threshold")]
+    pub scroll_threshold: f64,
+    /// Action for a left click on a desktop button
+    #[serde(default = "default_on_click", alias = "vdesk-on-click")]
+    pub vdesk_on_click: ClickAction,
+    /// Action for a middle click on a desktop button
+    #[serde(default = "default_on_click_secondary", alias = "vdesk-on-click-middle")]
+    pub vdesk_on_click_middle: ClickAction,
+    /// Action for a right click on a desktop button
+    #[serde(default = "default_on_click_secondary", alias = "vdesk-on-click-right")]
+    pub vdesk_on_click_right: ClickAction,
+    /// JSON file whose options override these ones; reloaded whenever it changes
+    #[serde(default, alias = "config-file")]
+    pub config_file: Option<String>,
+    /// Resync with Hyprland on `pkill -RTMIN+N waybar` for this N
+    #[serde(default)]
+    pub signal: Option<u32>,
+    /// Reload the configuration on `pkill -RTMIN+N waybar` for this N
+    #[serde(default, alias = "reload-signal")]
+    pub reload_signal: Option<u32>,
+    /// Log the performance metrics on `pkill -RTMIN+N waybar` for this N
+    #[serde(default, alias = "metrics-signal")]
+    pub metrics_signal: Option<u32>,
+    /// Keys that match no option, collected so typos can be reported
+    #[serde(flatten, skip_serializing)]
+    #[cfg_attr(test, schemars(skip))]
+    pub unknown_keys: HashMap<String, serde::de::IgnoredAny>,
 }
 
 // Default functions for serde
@@ -73,6 +359,14 @@ fn default_format() -> String {
     "{name}".to_string()
 }
 
+fn default_format_disconnected() -> String {
+    "disconnected".to_string()
+}
+
+fn default_format_error() -> String {
+    "error".to_string()
+}
+
 fn default_show_empty() -> bool {
     false
 }
@@ -94,10 +388,44 @@ fn default_retry_base_delay_ms() -> u64 {
     500
 }
 
+fn default_update_debounce_ms() -> u64 {
+    30
+}
+
+fn default_ipc_timeout_ms() -> u64 {
+    crate::hyprland::DEFAULT_IPC_TIMEOUT_MS
+}
+
+fn default_transition_duration_ms() -> u32 {
+    150
+}
+
+fn default_scroll_wrap() -> bool {
+    true
+}
+
+fn default_scroll_threshold() -> f64 {
+    1.0
+}
+
+fn default_on_click() -> ClickAction {
+    ClickAction::Switch
+}
+
+fn default_on_click_secondary() -> ClickAction {
+    ClickAction::None
+}
+
 impl Default for ModuleConfig {
     fn default() -> Self {
         Self {
             format: default_format(),
+            format_focused: None,
+            format_urgent: None,
+            format_populated: None,
+            format_empty: None,
+            format_disconnected: default_format_disconnected(),
+            format_error: default_format_error(),
             show_empty: default_show_empty(),
             separator: default_separator(),
             format_icons: HashMap::new(),
@@ -105,15 +433,93 @@ impl Default for ModuleConfig {
             sort_by: SortStrategy::default(),
             retry_max: default_retry_max(),
             retry_base_delay_ms: default_retry_base_delay_ms(),
+            update_debounce_ms: default_update_debounce_ms(),
+            ipc_timeout_ms: default_ipc_timeout_ms(),
+            max_reconnect_attempts: 0,
+            instance_signature: None,
+            transition_type: TransitionType::default(),
+            transition_duration_ms: default_transition_duration_ms(),
+            vdesk_on_scroll_up: None,
+            vdesk_on_scroll_down: None,
+            scroll_wrap: default_scroll_wrap(),
+            scroll_skip_empty: false,
+            scroll_threshold: default_scroll_threshold(),
+            vdesk_on_click: default_on_click(),
+            vdesk_on_click_middle: default_on_click_secondary(),
+            vdesk_on_click_right: default_on_click_secondary(),
+            config_file: None,
+            signal: None,
+            reload_signal: None,
+            metrics_signal: None,
+            unknown_keys: HashMap::new(),
         }
     }
 }
 
 impl ModuleConfig {
-    /// Validate configuration parameters
+    /// Parse the options from Waybar's JSON, never failing
+    ///
+    /// Options with a value of the wrong type or out of range fall back to their
+    /// defaults and are returned as errors so they can be reported together. When an
+    /// option is given in both spellings, the first one in key order wins and the
+    /// other is reported.
+    pub fn from_json_map(
+        map: serde_json::Map<String, serde_json::Value>,
+    ) -> (Self, Vec<crate::errors::VirtualDesktopError>) {
+        let known = known_keys();
+        let mut errors = Vec::new();
+        let mut accepted = serde_json::Map::new();
+
+        // Parse each option on its own so one bad value does not discard the rest
+        for (key, value) in map {
+            let single = serde_json::Value::Object(std::iter::once((key.clone(), value.clone())).collect());
+            match serde_json::from_value::<Self>(single) {
+                Ok(_) => {
+                    // Store options under their canonical name so kebab-case aliases cannot collide
+                    let canonical = key.replace('-', "_");
+                    let canonical = if known.contains(&canonical) { canonical } else { key.clone() };
+                    if accepted.contains_key(&canonical) {
+                        let shown = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+                        errors.push(crate::errors::VirtualDesktopError::invalid_config(
+                            &key,
+                            &shown,
+                            &format!("'{}' is already set, so this value is ignored", canonical),
+                        ));
+                        continue;
+                    }
+                    accepted.insert(canonical, value);
+                }
+                Err(e) => {
+                    let shown = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+                    errors.push(crate::errors::VirtualDesktopError::invalid_config(&key, &shown, &e.to_string()));
+                }
+            }
+        }
+
+        // Every option was accepted on its own and under a single name, so this only
+        // fails if options conflict in a way no single one shows
+        let mut config = serde_json::from_value(serde_json::Value::Object(accepted)).unwrap_or_else(|e| {
+            errors.push(crate::errors::VirtualDesktopError::invalid_config("config", "", &e.to_string()));
+            Self::default()
+        });
+        errors.extend(config.sanitize());
+        (config, errors)
+    }
+
+    /// Validate configuration parameters, returning the first problem found
     pub fn validate(&self) -> Result<(), crate::errors::VirtualDesktopError> {
+        match self.validation_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Every invalid field, so all problems can be reported at once
+    pub fn validation_errors(&self) -> Vec<crate::errors::VirtualDesktopError> {
+        let mut errors = Vec::new();
+
         if !self.format.contains('{') {
-            return Err(crate::errors::VirtualDesktopError::invalid_config(
+            errors.push(crate::errors::VirtualDesktopError::invalid_config(
                 "format",
                 &self.format,
                 "must contain at least one placeholder like {name}, {icon}, {id}, or {window_count}"
@@ -121,7 +527,7 @@ impl ModuleConfig {
         }
 
         if self.retry_max == 0 {
-            return Err(crate::errors::VirtualDesktopError::invalid_config(
+            errors.push(crate::errors::VirtualDesktopError::invalid_config(
                 "retry_max",
                 &self.retry_max.to_string(),
                 "must be greater than 0"
@@ -129,7 +535,7 @@ impl ModuleConfig {
         }
 
         if self.retry_max > 50 {
-            return Err(crate::errors::VirtualDesktopError::invalid_config(
+            errors.push(crate::errors::VirtualDesktopError::invalid_config(
                 "retry_max",
                 &self.retry_max.to_string(),
                 "must be 50 or less to prevent excessive delays"
@@ -137,29 +543,145 @@ impl ModuleConfig {
         }
 
         if self.retry_base_delay_ms > 10000 {
-            return Err(crate::errors::VirtualDesktopError::invalid_config(
+            errors.push(crate::errors::VirtualDesktopError::invalid_config(
                 "retry_base_delay_ms",
                 &self.retry_base_delay_ms.to_string(),
                 "must be 10000ms or less to prevent excessive delays"
             ));
         }
 
-        Ok(())
+        if self.update_debounce_ms > 1000 {
+            errors.push(crate::errors::VirtualDesktopError::invalid_config(
+                "update_debounce_ms",
+                &self.update_debounce_ms.to_string(),
+                "must be 1000ms or less to keep the bar responsive"
+            ));
+        }
+
+        if self.ipc_timeout_ms == 0 || self.ipc_timeout_ms > 30000 {
+            errors.push(crate::errors::VirtualDesktopError::invalid_config(
+                "ipc_timeout_ms",
+                &self.ipc_timeout_ms.to_string(),
+                "must be between 1 and 30000ms"
+            ));
+        }
+
+        if let Some(signature) = &self.instance_signature {
+            if crate::hyprland::validate_instance_signature(signature).is_err() {
+                errors.push(crate::errors::VirtualDesktopError::invalid_config(
+                    "instance_signature",
+                    signature,
+                    "must be alphanumeric with optional underscores/hyphens, 1-64 characters long"
+                ));
+            }
+        }
+
+        let signals = [
+            ("signal", self.signal),
+            ("reload_signal", self.reload_signal),
+            ("metrics_signal", self.metrics_signal),
+        ];
+        for (i, (field, offset)) in signals.iter().enumerate() {
+            let Some(offset) = offset else { continue };
+            if crate::signals::realtime_signal(*offset).is_none() {
+                errors.push(crate::errors::VirtualDesktopError::invalid_config(
+                    field,
+                    &offset.to_string(),
+                    &format!("must be between 1 and {}", crate::signals::max_signal_offset())
+                ));
+            } else if let Some((other, _)) = signals[..i].iter().find(|(_, earlier)| *earlier == Some(*offset)) {
+                errors.push(crate::errors::VirtualDesktopError::invalid_config(
+                    field,
+                    &offset.to_string(),
+                    &format!("is already used by {}", other)
+                ));
+            }
+        }
+
+        if self.transition_duration_ms > 2000 {
+            errors.push(crate::errors::VirtualDesktopError::invalid_config(
+                "transition_duration_ms",
+                &self.transition_duration_ms.to_string(),
+                "must be 2000ms or less"
+            ));
+        }
+
+        if !self.scroll_threshold.is_finite() || self.scroll_threshold < 0.0 {
+            errors.push(crate::errors::VirtualDesktopError::invalid_config(
+                "scroll_threshold",
+                &self.scroll_threshold.to_string(),
+                "must be a non-negative number"
+            ));
+        }
+
+        errors
+    }
+
+    /// Reset every invalid field to its default, returning the problems that were corrected
+    pub fn sanitize(&mut self) -> Vec<crate::errors::VirtualDesktopError> {
+        let errors = self.validation_errors();
+        let defaults = Self::default();
+
+        for error in &errors {
+            if let crate::errors::VirtualDesktopError::Configuration { field, .. } = error {
+                match field.as_str() {
+                    "format" => self.format = defaults.format.clone(),
+                    "retry_max" => self.retry_max = defaults.retry_max,
+                    "retry_base_delay_ms" => self.retry_base_delay_ms = defaults.retry_base_delay_ms,
+                    "update_debounce_ms" => self.update_debounce_ms = defaults.update_debounce_ms,
+                    "ipc_timeout_ms" => self.ipc_timeout_ms = defaults.ipc_timeout_ms,
+                    "instance_signature" => self.instance_signature = defaults.instance_signature.clone(),
+                    "signal" => self.signal = defaults.signal,
+                    "reload_signal" => self.reload_signal = defaults.reload_signal,
+                    "metrics_signal" => self.metrics_signal = defaults.metrics_signal,
+                    "transition_duration_ms" => self.transition_duration_ms = defaults.transition_duration_ms,
+                    "scroll_threshold" => self.scroll_threshold = defaults.scroll_threshold,
+                    _ => {}
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Effective animation duration, zero when transitions are disabled
+    pub fn effective_transition_duration_ms(&self) -> u32 {
+        match self.transition_type {
+            TransitionType::None => 0,
+            _ => self.transition_duration_ms,
+        }
+    }
+
+    /// Format string for a desktop in its current state
+    ///
+    /// Focused wins over urgent, which wins over populated or empty; states without
+    /// an override use `format`.
+    pub fn format_for(&self, vdesk: &VirtualDesktop) -> &str {
+        let format = if vdesk.focused {
+            &self.format_focused
+        } else if vdesk.urgent {
+            &self.format_urgent
+        } else if vdesk.populated {
+            &self.format_populated
+        } else {
+            &self.format_empty
+        };
+        format.as_deref().unwrap_or(&self.format)
     }
 
     /// Format virtual desktop display text
-    pub fn format_virtual_desktop(&self, name: &str, id: u32, window_count: u32) -> String {
+    pub fn format_virtual_desktop(&self, vdesk: &VirtualDesktop) -> String {
         let icon = self.format_icons
-            .get(&id.to_string())
-            .or_else(|| self.format_icons.get(name))
+            .get(&vdesk.id.to_string())
+            .or_else(|| self.format_icons.get(&vdesk.name))
             .map(|s| s.as_str())
             .unwrap_or("");
         
-        self.format
-            .replace("{name}", name)
+        self.format_for(vdesk)
+            .replace("{name}", &vdesk.name)
             .replace("{icon}", icon)
-            .replace("{id}", &id.to_string())
-            .replace("{window_count}", &window_count.to_string())
+            .replace("{id}", &vdesk.id.to_string())
+            .replace("{window_count}", &vdesk.window_count.to_string())
     }
     
     /// Generate tooltip text
@@ -190,6 +712,12 @@ mod tests {
 
         let config = ModuleConfig {
             format: "{icon} {name} ({window_count})".to_string(),
+            format_focused: None,
+            format_urgent: None,
+            format_populated: None,
+            format_empty: None,
+            format_disconnected: "offline".to_string(),
+            format_error: "error".to_string(),
             show_empty: true,
             separator: " | ".to_string(),
             format_icons,
@@ -197,15 +725,39 @@ mod tests {
             sort_by: SortStrategy::Number,
             retry_max: 10,
             retry_base_delay_ms: 500,
+            update_debounce_ms: 30,
+            ipc_timeout_ms: 2000,
+            max_reconnect_attempts: 0,
+            instance_signature: None,
+            transition_type: TransitionType::Crossfade,
+            transition_duration_ms: 150,
+            vdesk_on_scroll_up: None,
+            vdesk_on_scroll_down: None,
+            scroll_wrap: true,
+            scroll_skip_empty: false,
+            scroll_threshold: 1.0,
+            vdesk_on_click: ClickAction::Switch,
+            vdesk_on_click_middle: ClickAction::None,
+            vdesk_on_click_right: ClickAction::None,
+            config_file: None,
+            signal: None,
+            reload_signal: None,
+            metrics_signal: None,
+            unknown_keys: HashMap::new(),
+        };
+
+        let vdesk = |id, name: &str, window_count| VirtualDesktop {
+            window_count,
+            ..VirtualDesktop::new(id, name.to_string())
         };
 
-        let result = config.format_virtual_desktop("Home", 1, 3);
+        let result = config.format_virtual_desktop(&vdesk(1, "Home", 3));
         assert_eq!(result, "🏠 Home (3)");
 
-        let result = config.format_virtual_desktop("Work", 2, 5);
+        let result = config.format_virtual_desktop(&vdesk(2, "Work", 5));
         assert_eq!(result, "💼 Work (5)");
 
-        let result = config.format_virtual_desktop("Other", 3, 0);
+        let result = config.format_virtual_desktop(&vdesk(3, "Other", 0));
         assert_eq!(result, " Other (0)");
 
         let tooltip = config.format_tooltip("Home", 1, 3, true);
@@ -215,6 +767,31 @@ mod tests {
         assert_eq!(tooltip, "Virtual Desktop 2: Work (5 windows)");
     }
 
+    #[test]
+    fn test_state_formats_fall_back_to_format() {
+        let config: ModuleConfig = serde_json::from_value(serde_json::json!({
+            "format": "{name}",
+            "format-focused": "[{name}]",
+            "format-empty": "·",
+            "format_urgent": "{name}!"
+        })).unwrap();
+
+        let vdesk = |focused, urgent, populated| VirtualDesktop {
+            focused,
+            urgent,
+            populated,
+            ..VirtualDesktop::new(1, "Focus".to_string())
+        };
+
+        assert_eq!(config.format_virtual_desktop(&vdesk(true, false, true)), "[Focus]");
+        // Focus takes precedence over urgency
+        assert_eq!(config.format_virtual_desktop(&vdesk(true, true, false)), "[Focus]");
+        assert_eq!(config.format_virtual_desktop(&vdesk(false, true, true)), "Focus!");
+        assert_eq!(config.format_virtual_desktop(&vdesk(false, false, false)), "·");
+        // No populated override, so `format` is used
+        assert_eq!(config.format_virtual_desktop(&vdesk(false, false, true)), "Focus");
+    }
+
     #[test]
     fn test_default_config() {
         let config = ModuleConfig::default();
@@ -227,6 +804,21 @@ mod tests {
         assert_eq!(config.sort_by, SortStrategy::Number);
         assert_eq!(config.retry_max, 10);
         assert_eq!(config.retry_base_delay_ms, 500);
+        assert_eq!(config.update_debounce_ms, 30);
+        assert_eq!(config.ipc_timeout_ms, 2000);
+        assert_eq!(config.format_disconnected, "disconnected");
+        assert_eq!(config.format_error, "error");
+        assert_eq!(config.max_reconnect_attempts, 0);
+        assert_eq!(config.transition_type, TransitionType::Crossfade);
+        assert_eq!(config.transition_duration_ms, 150);
+        assert!(config.vdesk_on_scroll_up.is_none());
+        assert!(config.vdesk_on_scroll_down.is_none());
+        assert!(config.scroll_wrap);
+        assert!(!config.scroll_skip_empty);
+        assert_eq!(config.scroll_threshold, 1.0);
+        assert_eq!(config.vdesk_on_click, ClickAction::Switch);
+        assert_eq!(config.vdesk_on_click_middle, ClickAction::None);
+        assert_eq!(config.vdesk_on_click_right, ClickAction::None);
     }
 
     #[test]
@@ -245,7 +837,7 @@ mod tests {
         let config: ModuleConfig = serde_json::from_str(json).unwrap();
         assert_eq!(config.retry_max, 5);
         assert_eq!(config.retry_base_delay_ms, 1000);
-        assert_eq!(config.show_empty, false);
+        assert!(!config.show_empty);
         assert_eq!(config.separator, " ");
     }
 
@@ -264,10 +856,10 @@ mod tests {
 
         let config: ModuleConfig = serde_json::from_str(direct_json).unwrap();
         assert_eq!(config.format, "{icon} {name}");
-        assert_eq!(config.show_empty, true);
+        assert!(config.show_empty);
         assert_eq!(config.separator, " | ");
         assert_eq!(config.format_icons.get("1"), Some(&"🏠".to_string()));
-        assert_eq!(config.show_window_count, true);
+        assert!(config.show_window_count);
         assert_eq!(config.sort_by, SortStrategy::FocusedFirst);
         assert_eq!(config.retry_max, 15);
         assert_eq!(config.retry_base_delay_ms, 750);
@@ -281,10 +873,10 @@ mod tests {
 
         let config: ModuleConfig = serde_json::from_str(minimal_json).unwrap();
         assert_eq!(config.format, "{name}");
-        assert_eq!(config.show_empty, false);
+        assert!(!config.show_empty);
         assert_eq!(config.separator, " ");
         assert!(config.format_icons.is_empty());
-        assert_eq!(config.show_window_count, false);
+        assert!(!config.show_window_count);
         assert_eq!(config.sort_by, SortStrategy::Number);
         assert_eq!(config.retry_max, 10);
         assert_eq!(config.retry_base_delay_ms, 500);
@@ -318,6 +910,213 @@ mod tests {
             ..Default::default()
         };
         assert!(invalid_delay.validate().is_err());
+
+        let invalid_debounce = ModuleConfig {
+            update_debounce_ms: 5000,
+            ..Default::default()
+        };
+        assert!(invalid_debounce.validate().is_err());
+
+        let invalid_timeout = ModuleConfig {
+            ipc_timeout_ms: 0,
+            ..Default::default()
+        };
+        assert!(invalid_timeout.validate().is_err());
+
+        let invalid_signature = ModuleConfig {
+            instance_signature: Some("../other".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid_signature.validate().is_err());
+
+        let valid_signature = ModuleConfig {
+            instance_signature: Some("abc123_1700000000_1234".to_string()),
+            ..Default::default()
+        };
+        assert!(valid_signature.validate().is_ok());
+
+        let invalid_transition = ModuleConfig {
+            transition_duration_ms: 5000,
+            ..Default::default()
+        };
+        assert!(invalid_transition.validate().is_err());
+
+        let several_invalid = ModuleConfig {
+            format: "no placeholders".to_string(),
+            retry_max: 0,
+            ipc_timeout_ms: 0,
+            ..Default::default()
+        };
+        let errors = several_invalid.validation_errors();
+        assert_eq!(errors.len(), 3);
+
+        let invalid_threshold = ModuleConfig {
+            scroll_threshold: -1.0,
+            ..Default::default()
+        };
+        assert!(invalid_threshold.validate().is_err());
+
+        let invalid_signal = ModuleConfig {
+            reload_signal: Some(0),
+            ..Default::default()
+        };
+        assert!(invalid_signal.validate().is_err());
+
+        let shared_signal = ModuleConfig {
+            signal: Some(8),
+            metrics_signal: Some(8),
+            ..Default::default()
+        };
+        let errors = shared_signal.validation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("metrics_signal"));
+    }
+
+    #[test]
+    fn test_sanitize_resets_only_invalid_fields() {
+        let mut config = ModuleConfig {
+            format: "no placeholders".to_string(),
+            show_empty: true,
+            retry_max: 0,
+            scroll_threshold: f64::NAN,
+            update_debounce_ms: 100,
+            ..Default::default()
+        };
+
+        let errors = config.sanitize();
+        assert_eq!(errors.len(), 3);
+        assert!(config.validate().is_ok());
+
+        // Invalid fields fall back to defaults
+        assert_eq!(config.format, "{name}");
+        assert_eq!(config.retry_max, 10);
+        assert_eq!(config.scroll_threshold, 1.0);
+
+        // Valid customizations are kept
+        assert!(config.show_empty);
+        assert_eq!(config.update_debounce_ms, 100);
+    }
+
+    #[test]
+    fn test_every_option_accepts_kebab_and_snake_case() {
+        for key in known_keys() {
+            let value = serde_json::to_value(ModuleConfig::default()).unwrap()[&key].clone();
+            for spelling in [key.clone(), key.replace('_', "-")] {
+                let json = serde_json::json!({ spelling.clone(): value });
+                let config: ModuleConfig = serde_json::from_value(json)
+                    .unwrap_or_else(|e| panic!("'{}' should be accepted: {}", spelling, e));
+                assert_eq!(config.format, "{name}");
+            }
+        }
+
+        let config: ModuleConfig = serde_json::from_str(r#"{"show-empty": true, "sort-by": "name"}"#).unwrap();
+        assert!(config.show_empty);
+        assert_eq!(config.sort_by, SortStrategy::Name);
+    }
+
+    #[test]
+    fn test_from_json_map_falls_back_per_option() {
+        let json = serde_json::json!({
+            "show-empty": true,
+            "sort_by": "alphabetical",
+            "retry_max": "ten",
+            "update_debounce_ms": 5000,
+            "format": "{icon}",
+            "formt": "{name}"
+        });
+        let serde_json::Value::Object(map) = json else { unreachable!() };
+
+        let (config, errors) = ModuleConfig::from_json_map(map);
+        let mut fields: Vec<String> = errors
+            .iter()
+            .map(|e| match e {
+                crate::errors::VirtualDesktopError::Configuration { field, .. } => field.clone(),
+                other => panic!("unexpected error {:?}", other),
+            })
+            .collect();
+        fields.sort();
+        assert_eq!(fields, vec!["retry_max", "sort_by", "update_debounce_ms"]);
+
+        // Valid options are kept and invalid ones use their defaults
+        assert!(config.show_empty);
+        assert_eq!(config.format, "{icon}");
+        assert_eq!(config.sort_by, SortStrategy::Number);
+        assert_eq!(config.retry_max, 10);
+        assert_eq!(config.update_debounce_ms, 30);
+        assert!(config.unknown_keys.contains_key("formt"));
+    }
+
+    #[test]
+    fn test_from_json_map_keeps_options_given_in_both_spellings() {
+        let json = serde_json::json!({
+            "show_empty": false,
+            "show-empty": true,
+            "format": "<{name}>",
+            "sort-by": "name",
+            "retry_max": 3
+        });
+        let serde_json::Value::Object(map) = json else { unreachable!() };
+
+        let (config, errors) = ModuleConfig::from_json_map(map);
+        assert_eq!(errors.len(), 1);
+        let message = errors[0].to_string();
+        assert!(message.contains("show_empty"), "{message}");
+        assert!(message.contains("already set"), "{message}");
+
+        // `show-empty` sorts first, and nothing else is discarded
+        assert!(config.show_empty);
+        assert_eq!(config.format, "<{name}>");
+        assert_eq!(config.sort_by, SortStrategy::Name);
+        assert_eq!(config.retry_max, 3);
+        assert!(config.unknown_keys.is_empty());
+    }
+
+    /// Schema of the `cffi/virtual-desktops` block, checked in for editor autocompletion
+    fn config_schema() -> serde_json::Value {
+        let mut schema = serde_json::to_value(schemars::schema_for!(ModuleConfig)).unwrap();
+        schema["title"] = "cffi/virtual-desktops".into();
+        schema["properties"]["module_path"] = serde_json::json!({
+            "description": "Path to the compiled libwaybar_vd.so",
+            "type": "string"
+        });
+        schema
+    }
+
+    #[test]
+    fn test_json_schema_is_up_to_date() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("schema/waybar-vd.schema.json");
+        let generated = serde_json::to_string_pretty(&config_schema()).unwrap() + "\n";
+        if std::env::var_os("UPDATE_SCHEMA").is_some() {
+            std::fs::write(&path, &generated).unwrap();
+        }
+
+        let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "schema/waybar-vd.schema.json is stale, regenerate it with `UPDATE_SCHEMA=1 cargo test`"
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_suggestions() {
+        let unknown = find_unknown_keys(["show-emtpy", "sort_bye", "module_path", "tooltip", "banana"]);
+        assert_eq!(
+            unknown,
+            vec![
+                UnknownKey { key: "banana".to_string(), suggestion: None },
+                UnknownKey { key: "show-emtpy".to_string(), suggestion: Some("show-empty".to_string()) },
+                UnknownKey { key: "sort_bye".to_string(), suggestion: Some("sort_by".to_string()) },
+            ]
+        );
+        assert_eq!(unknown[1].to_string(), "'show-emtpy' (did you mean 'show-empty'?)");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("sort_by", "sort_by"), 0);
+        assert_eq!(edit_distance("show_emtpy", "show_empty"), 2);
+        assert_eq!(edit_distance("format", "formats"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
     }
 
     #[test]
@@ -332,4 +1131,85 @@ mod tests {
         assert_eq!(SortStrategy::Name.to_string(), "name");
         assert_eq!(SortStrategy::FocusedFirst.to_string(), "focused-first");
     }
+
+    #[test]
+    fn test_scroll_config_waybar_style_keys() {
+        let json = r#"{
+            "vdesk-on-scroll-up": "hyprctl dispatch prevdesk",
+            "vdesk-on-scroll-down": "hyprctl dispatch nextdesk",
+            "scroll-wrap": false,
+            "scroll-skip-empty": true,
+            "scroll-threshold": 4.5
+        }"#;
+
+        let config: ModuleConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.vdesk_on_scroll_up.as_deref(), Some("hyprctl dispatch prevdesk"));
+        assert_eq!(config.vdesk_on_scroll_down.as_deref(), Some("hyprctl dispatch nextdesk"));
+        assert!(!config.scroll_wrap);
+        assert!(config.scroll_skip_empty);
+        assert_eq!(config.scroll_threshold, 4.5);
+    }
+
+    #[test]
+    fn test_click_action_parsing() {
+        let json = r#"{
+            "vdesk_on_click": "move-active-window-here",
+            "vdesk-on-click-middle": "reset",
+            "vdesk-on-click-right": "exec:notify-send {name}"
+        }"#;
+
+        let config: ModuleConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.vdesk_on_click, ClickAction::MoveActiveWindowHere);
+        assert_eq!(config.vdesk_on_click_middle, ClickAction::Reset);
+        assert_eq!(config.vdesk_on_click_right, ClickAction::Command("notify-send {name}".to_string()));
+
+        assert_eq!(ClickAction::try_from("switch".to_string()), Ok(ClickAction::Switch));
+        assert_eq!(ClickAction::try_from("rename".to_string()), Ok(ClickAction::Rename));
+        assert_eq!(ClickAction::try_from("none".to_string()), Ok(ClickAction::None));
+        assert_eq!(ClickAction::try_from(String::new()), Ok(ClickAction::None));
+        assert_eq!(ClickAction::MoveActiveWindowHere.to_string(), "move-active-window-here");
+        assert_eq!(ClickAction::Command("notify-send {name}".to_string()).to_string(), "exec:notify-send {name}");
+    }
+
+    #[test]
+    fn test_unknown_click_actions_are_reported() {
+        assert!(ClickAction::try_from("swtich".to_string()).unwrap_err().contains("unknown click action 'swtich'"));
+        assert!(ClickAction::try_from("Switch".to_string()).is_err());
+        assert!(ClickAction::try_from("exec: ".to_string()).is_err());
+
+        let json = serde_json::json!({"vdesk-on-click": "swtich", "vdesk-on-click-right": "rename"});
+        let serde_json::Value::Object(map) = json else { unreachable!() };
+        let (config, errors) = ModuleConfig::from_json_map(map);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("vdesk-on-click"));
+        assert_eq!(config.vdesk_on_click, ClickAction::Switch);
+        assert_eq!(config.vdesk_on_click_right, ClickAction::Rename);
+
+        // Waybar runs its own on-click keys, so they are not ours to report
+        assert!(find_unknown_keys(["on-click", "on-scroll-up"]).is_empty());
+    }
+
+    #[test]
+    fn test_transition_type_parsing() {
+        assert_eq!("none".parse::<TransitionType>().unwrap(), TransitionType::None);
+        assert_eq!("crossfade".parse::<TransitionType>().unwrap(), TransitionType::Crossfade);
+        assert_eq!("slide-left".parse::<TransitionType>().unwrap(), TransitionType::SlideLeft);
+        assert_eq!("slide-right".parse::<TransitionType>().unwrap(), TransitionType::SlideRight);
+
+        assert!("fade".parse::<TransitionType>().is_err());
+
+        assert_eq!(TransitionType::SlideLeft.to_string(), "slide-left");
+
+        let json = r#"{ "transition_type": "slide-right", "transition_duration_ms": 250 }"#;
+        let config: ModuleConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.transition_type, TransitionType::SlideRight);
+        assert_eq!(config.effective_transition_duration_ms(), 250);
+
+        // Reduced motion: no transition means no delay at all
+        let config = ModuleConfig {
+            transition_type: TransitionType::None,
+            ..Default::default()
+        };
+        assert_eq!(config.effective_transition_duration_ms(), 0);
+    }
 }
@@ -42,6 +42,27 @@ pub enum VirtualDesktopError {
         last_error: String,
     },
 
+    #[error("Hyprland rejected command '{command}': {response}")]
+    CommandRejected {
+        command: String,
+        response: String,
+    },
+
+    #[error("Hyprland returned an empty response to command '{command}'")]
+    EmptyResponse { command: String },
+
+    #[error("Hyprland IPC timed out after {timeout_ms}ms: {operation}")]
+    Timeout { operation: String, timeout_ms: u64 },
+
+    #[error("No virtual desktop is named '{name}'")]
+    UnknownDesktop { name: String },
+
+    #[error("Virtual desktop name '{name}' is ambiguous: matches {candidates}")]
+    AmbiguousDesktop { name: String, candidates: String },
+
+    #[error("Invalid action '{action}': {reason}")]
+    InvalidAction { action: String, reason: String },
+
     #[error("JSON processing failed: {operation} - {source}")]
     JsonError {
         operation: String,
@@ -62,6 +83,12 @@ impl VirtualDesktopError {
             Self::StateParsing { .. } => ErrorSeverity::Recoverable,
             Self::WidgetOperation { .. } => ErrorSeverity::Minor,
             Self::RetryExhausted { .. } => ErrorSeverity::Critical,
+            Self::CommandRejected { .. } => ErrorSeverity::Critical,
+            Self::EmptyResponse { .. } => ErrorSeverity::Recoverable,
+            Self::Timeout { .. } => ErrorSeverity::Recoverable,
+            Self::UnknownDesktop { .. } => ErrorSeverity::Minor,
+            Self::AmbiguousDesktop { .. } => ErrorSeverity::Minor,
+            Self::InvalidAction { .. } => ErrorSeverity::Minor,
             Self::JsonError { .. } => ErrorSeverity::Recoverable,
             Self::Internal { .. } => ErrorSeverity::Critical,
         }
@@ -91,6 +118,37 @@ impl VirtualDesktopError {
         }
     }
 
+    /// Create error for a command Hyprland answered with error text
+    pub fn command_rejected(command: &str, response: &str) -> Self {
+        Self::CommandRejected {
+            command: command.to_string(),
+            response: response.to_string(),
+        }
+    }
+
+    /// Create error for a command Hyprland answered with nothing
+    pub fn empty_response(command: &str) -> Self {
+        Self::EmptyResponse {
+            command: command.to_string(),
+        }
+    }
+
+    /// Create IPC timeout error
+    pub fn timeout(operation: &str, timeout_ms: u64) -> Self {
+        Self::Timeout {
+            operation: operation.to_string(),
+            timeout_ms,
+        }
+    }
+
+    /// Create error for an action string that cannot be run
+    pub fn invalid_action(action: &str, reason: &str) -> Self {
+        Self::InvalidAction {
+            action: action.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
     /// Create JSON processing error with context
     pub fn from_json_error(operation: &str, source: serde_json::Error) -> Self {
         Self::JsonError {
@@ -132,6 +190,18 @@ mod tests {
             last_error: "connection timeout".to_string(),
         };
         assert_eq!(retry_error.severity(), ErrorSeverity::Critical);
+
+        let rejected_error = VirtualDesktopError::command_rejected("dispatch nextdesk", "Invalid dispatcher");
+        assert_eq!(rejected_error.severity(), ErrorSeverity::Critical);
+
+        let empty_error = VirtualDesktopError::empty_response("j/printstate");
+        assert_eq!(empty_error.severity(), ErrorSeverity::Recoverable);
+
+        let timeout_error = VirtualDesktopError::timeout("read response to 'j/printstate'", 2000);
+        assert_eq!(timeout_error.severity(), ErrorSeverity::Recoverable);
+
+        let action_error = VirtualDesktopError::invalid_action("jump", "unknown verb");
+        assert_eq!(action_error.severity(), ErrorSeverity::Minor);
     }
 
     #[test]
@@ -184,6 +254,17 @@ mod tests {
         assert!(error_string.contains("unknown placeholder"));
     }
 
+    #[test]
+    fn test_command_error_display_names_command() {
+        let error = VirtualDesktopError::command_rejected("dispatch vdesk 9", "invalid argument");
+        let error_string = error.to_string();
+        assert!(error_string.contains("dispatch vdesk 9"));
+        assert!(error_string.contains("invalid argument"));
+
+        let error = VirtualDesktopError::empty_response("j/printstate");
+        assert!(error.to_string().contains("j/printstate"));
+    }
+
     #[test]
     fn test_json_error_conversion() {
         let json_error = serde_json::from_str::<()>("invalid json").unwrap_err();
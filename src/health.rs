@@ -0,0 +1,106 @@
+//! Connection health of the Hyprland monitor
+
+use serde::Serialize;
+
+/// Health of the connection to Hyprland as seen by the monitor supervisor
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[repr(u8)]
+pub enum HealthState {
+    /// Not connected yet since the module started
+    #[default]
+    Connecting = 0,
+    /// Connected and receiving events
+    Live = 1,
+    /// Recently lost the connection or failed to refresh, retrying quickly
+    Degraded = 2,
+    /// Unreachable after repeated attempts, retrying with backoff
+    Disconnected = 3,
+    /// Monitoring gave up, after a critical error or once the reconnect attempts ran out
+    Stopped = 4,
+}
+
+impl HealthState {
+    /// All states, for clearing their CSS classes
+    pub const ALL: [HealthState; 5] = [
+        Self::Connecting,
+        Self::Live,
+        Self::Degraded,
+        Self::Disconnected,
+        Self::Stopped,
+    ];
+
+    /// Decode a state stored as its discriminant
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Live,
+            2 => Self::Degraded,
+            3 => Self::Disconnected,
+            4 => Self::Stopped,
+            _ => Self::Connecting,
+        }
+    }
+
+    /// CSS class applied to the module container in this state
+    pub fn css_class(self) -> &'static str {
+        match self {
+            Self::Connecting => "connecting",
+            Self::Live => "live",
+            Self::Degraded => "degraded",
+            Self::Disconnected => "disconnected",
+            Self::Stopped => "stopped",
+        }
+    }
+
+    /// Tooltip explaining the state, if it needs one
+    pub fn tooltip(self) -> Option<&'static str> {
+        match self {
+            Self::Connecting => Some("Connecting to Hyprland..."),
+            Self::Live => None,
+            Self::Degraded => Some("Hyprland connection degraded, retrying..."),
+            Self::Disconnected => Some("Disconnected from Hyprland, reconnecting..."),
+            Self::Stopped => Some("Stopped monitoring Hyprland, click to reconnect"),
+        }
+    }
+}
+
+/// Health state published to the UI along with the error behind it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthReport {
+    pub state: HealthState,
+    /// Message of the most recent error, cleared once live again
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    pub fn new(state: HealthState, error: Option<String>) -> Self {
+        Self { state, error }
+    }
+}
+
+impl std::fmt::Display for HealthState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.css_class())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_state_round_trips_through_u8() {
+        for state in HealthState::ALL {
+            assert_eq!(HealthState::from_u8(state as u8), state);
+        }
+        assert_eq!(HealthState::from_u8(42), HealthState::Connecting);
+    }
+
+    #[test]
+    fn test_only_live_has_no_tooltip() {
+        for state in HealthState::ALL {
+            assert_eq!(state.tooltip().is_none(), state == HealthState::Live);
+        }
+        assert_eq!(HealthState::Disconnected.to_string(), "disconnected");
+    }
+}
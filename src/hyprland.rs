@@ -1,12 +1,18 @@
 //! Hyprland IPC communication for virtual desktop management
 
 // src/hyprland.rs
+use crate::config::ModuleConfig;
+use crate::errors::VirtualDesktopError;
 use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::env;
-use std::path::PathBuf;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::stream::{self, Stream, StreamExt};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
 use tokio::net::UnixStream;
 
 /// Regex pattern for validating Hyprland instance signatures
@@ -16,7 +22,7 @@ static INSTANCE_SIGNATURE_PATTERN: Lazy<Regex> = Lazy::new(|| {
 });
 
 /// Validates Hyprland instance signature
-fn validate_instance_signature(signature: &str) -> Result<()> {
+pub(crate) fn validate_instance_signature(signature: &str) -> Result<()> {
     if signature.is_empty() {
         return Err(anyhow!("Invalid HYPRLAND_INSTANCE_SIGNATURE: empty signature"));
     }
@@ -33,39 +39,341 @@ fn validate_instance_signature(signature: &str) -> Result<()> {
     Ok(())
 }
 
+/// Directory holding the sockets of one Hyprland instance
+fn instance_dir(runtime_dir: &Path, signature: &str) -> PathBuf {
+    runtime_dir.join("hypr").join(signature)
+}
+
+/// Whether an instance directory has both the command and event sockets
+fn has_sockets(dir: &Path) -> bool {
+    dir.join(".socket.sock").exists() && dir.join(".socket2.sock").exists()
+}
+
+/// Whether a Hyprland instance is accepting connections on its command socket
+async fn is_responsive(dir: &Path, timeout_ms: u64) -> bool {
+    let connect = UnixStream::connect(dir.join(".socket.sock"));
+    matches!(
+        tokio::time::timeout(tokio::time::Duration::from_millis(timeout_ms), connect).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Pick the Hyprland instance to talk to.
+///
+/// A configured signature always wins. Otherwise the environment signature is used while
+/// its instance is alive, falling back to scanning the runtime directory.
+async fn resolve_instance_signature(
+    runtime_dir: &Path,
+    configured: Option<&str>,
+    from_env: Option<&str>,
+    timeout_ms: u64,
+) -> Result<String> {
+    if let Some(signature) = configured {
+        validate_instance_signature(signature)?;
+        log::debug!("Using configured Hyprland instance '{}'", signature);
+        return Ok(signature.to_string());
+    }
+
+    let stale_dir = match from_env {
+        Some(signature) => {
+            validate_instance_signature(signature)?;
+            let dir = instance_dir(runtime_dir, signature);
+            if has_sockets(&dir) && is_responsive(&dir, timeout_ms).await {
+                return Ok(signature.to_string());
+            }
+            log::warn!("Hyprland instance '{}' from HYPRLAND_INSTANCE_SIGNATURE is not running, searching for another", signature);
+            Some(dir)
+        }
+        None => {
+            log::info!("HYPRLAND_INSTANCE_SIGNATURE not set, searching for a running Hyprland instance");
+            None
+        }
+    };
+
+    if let Some(signature) = discover_instance(runtime_dir, timeout_ms).await {
+        log::info!("Discovered Hyprland instance '{}'", signature);
+        return Ok(signature);
+    }
+
+    let hypr_dir = runtime_dir.join("hypr");
+    match stale_dir {
+        Some(dir) => Err(anyhow!(
+            "Hyprland command socket not found: {:?}, and no other running instance in {:?}",
+            dir.join(".socket.sock"),
+            hypr_dir
+        )),
+        None => Err(anyhow!(
+            "HYPRLAND_INSTANCE_SIGNATURE not set and no running Hyprland instance found in {:?}",
+            hypr_dir
+        )),
+    }
+}
+
+/// Find the most recently started Hyprland instance that accepts connections
+async fn discover_instance(runtime_dir: &Path, timeout_ms: u64) -> Option<String> {
+    let entries = std::fs::read_dir(runtime_dir.join("hypr")).ok()?;
+
+    let mut candidates: Vec<(std::time::SystemTime, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let signature = entry.file_name().into_string().ok()?;
+            // Never build socket paths from directory names that would fail validation
+            if !INSTANCE_SIGNATURE_PATTERN.is_match(&signature) || !has_sockets(&entry.path()) {
+                return None;
+            }
+            let modified = std::fs::metadata(entry.path().join(".socket.sock"))
+                .and_then(|meta| meta.modified())
+                .unwrap_or(std::time::UNIX_EPOCH);
+            Some((modified, signature))
+        })
+        .collect();
+
+    // Newest sockets first, so a restarted compositor wins over leftovers from old sessions
+    candidates.sort_by(|a, b| b.cmp(a));
+
+    for (_, signature) in candidates {
+        if is_responsive(&instance_dir(runtime_dir, &signature), timeout_ms).await {
+            return Some(signature);
+        }
+        log::debug!("Skipping unresponsive Hyprland instance '{}'", signature);
+    }
+    None
+}
+
+/// Whether an error means the socket itself is unusable, so the client should be rebuilt.
+///
+/// Rejected commands and empty responses come from a live compositor and are not connection errors.
+pub fn is_connection_error(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<std::io::Error>().is_some()
+        || matches!(
+            error.downcast_ref::<VirtualDesktopError>(),
+            Some(VirtualDesktopError::Timeout { .. } | VirtualDesktopError::IpcConnection { .. })
+        )
+}
+
+/// Validates a virtual desktop ID passed to a dispatcher
+fn validate_vdesk_id(vdesk_id: u32) -> Result<()> {
+    if vdesk_id == 0 {
+        return Err(anyhow!("Invalid virtual desktop ID 0: IDs start at 1"));
+    }
+    Ok(())
+}
+
+/// Validates a virtual desktop name passed to a dispatcher
+fn validate_vdesk_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(anyhow!("Invalid virtual desktop name: name is empty"));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(anyhow!("Invalid virtual desktop name {:?}: contains control characters", name));
+    }
+    // The plugin reads a numeric argument as an ID
+    if name.trim().parse::<u32>().is_ok() {
+        return Err(anyhow!("Invalid virtual desktop name '{}': numeric names are read as IDs", name));
+    }
+    Ok(())
+}
+
+/// Classification of a response read from the command socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandResponse {
+    /// Hyprland acknowledged the command with `ok`
+    Ok,
+    /// Hyprland closed the socket without answering
+    Empty,
+    /// Hyprland answered with error text, e.g. `Invalid dispatcher`
+    Error(String),
+}
+
+impl CommandResponse {
+    /// Classify the response to a dispatch or keyword command
+    pub fn classify(response: &str) -> Self {
+        match response.trim() {
+            "ok" => Self::Ok,
+            "" => Self::Empty,
+            error => Self::Error(error.to_string()),
+        }
+    }
+
+    /// Convert into a result, naming the command in the error
+    pub fn into_result(self, command: &str) -> std::result::Result<(), VirtualDesktopError> {
+        match self {
+            Self::Ok => Ok(()),
+            Self::Empty => Err(VirtualDesktopError::empty_response(command)),
+            Self::Error(response) => Err(VirtualDesktopError::command_rejected(command, &response)),
+        }
+    }
+}
+
+/// Checks that Hyprland answered a dispatch or keyword command with `ok`
+fn check_dispatch_response(command: &str, response: &str) -> Result<()> {
+    CommandResponse::classify(response)
+        .into_result(command)
+        .map_err(Into::into)
+}
+
+/// Checks that Hyprland answered a JSON query with JSON rather than error text
+fn check_query_response(command: &str, response: String) -> Result<String> {
+    let trimmed = response.trim_start();
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        return Ok(response);
+    }
+
+    let error = match CommandResponse::classify(&response) {
+        CommandResponse::Empty => VirtualDesktopError::empty_response(command),
+        // A JSON query never legitimately answers `ok`, so treat it as error text too
+        CommandResponse::Ok | CommandResponse::Error(_) => {
+            VirtualDesktopError::command_rejected(command, response.trim())
+        }
+    };
+    Err(error.into())
+}
+
+/// Typed event received on the Hyprland event socket (`NAME>>DATA`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HyprlandEvent {
+    /// `vdesk>>ID` - virtual desktop switched
+    VirtualDesktop { id: u32 },
+    /// `workspace>>NAME` - active workspace changed
+    Workspace { name: String },
+    /// `workspacev2>>ID,NAME` - active workspace changed
+    WorkspaceV2 { id: i32, name: String },
+    /// `openwindow>>ADDRESS,WORKSPACE,CLASS,TITLE` - window opened
+    OpenWindow {
+        address: String,
+        workspace: String,
+        class: String,
+        title: String,
+    },
+    /// `closewindow>>ADDRESS` - window closed
+    CloseWindow { address: String },
+    /// `movewindow>>ADDRESS,WORKSPACE` - window moved to another workspace
+    MoveWindow { address: String, workspace: String },
+    /// `focusedmon>>MONITOR,WORKSPACE` - monitor focus changed
+    FocusedMonitor { monitor: String, workspace: String },
+    /// `activewindow>>CLASS,TITLE` - active window changed
+    ActiveWindow { class: String, title: String },
+    /// `urgent>>ADDRESS` - window requested attention
+    Urgent { address: String },
+    /// `monitoradded>>NAME` - monitor connected
+    MonitorAdded { name: String },
+    /// `monitorremoved>>NAME` - monitor disconnected
+    MonitorRemoved { name: String },
+    /// `createworkspace>>NAME` - workspace created
+    CreateWorkspace { name: String },
+    /// `destroyworkspace>>NAME` - workspace destroyed
+    DestroyWorkspace { name: String },
+    /// `configreloaded>>` - Hyprland configuration reloaded
+    ConfigReloaded,
+    /// Any event not covered above, or with malformed data
+    Unknown { name: String, data: String },
+}
+
+impl HyprlandEvent {
+    /// Whether this event can change virtual desktop state (focus, window counts, populated or urgent flags)
+    pub fn affects_virtual_desktops(&self) -> bool {
+        matches!(
+            self,
+            Self::VirtualDesktop { .. }
+                | Self::OpenWindow { .. }
+                | Self::CloseWindow { .. }
+                | Self::MoveWindow { .. }
+                | Self::CreateWorkspace { .. }
+                | Self::DestroyWorkspace { .. }
+                | Self::Urgent { .. }
+        )
+    }
+
+    /// Parse a raw event line in the `NAME>>DATA` wire format
+    pub fn parse(line: &str) -> Self {
+        let (name, data) = line.split_once(">>").unwrap_or((line, ""));
+
+        let parsed = match name {
+            "vdesk" => data.trim().parse().ok().map(|id| Self::VirtualDesktop { id }),
+            "workspace" => Some(Self::Workspace { name: data.to_string() }),
+            "workspacev2" => data.split_once(',').and_then(|(id, name)| {
+                id.parse().ok().map(|id| Self::WorkspaceV2 { id, name: name.to_string() })
+            }),
+            "openwindow" => {
+                // The title is last and may itself contain commas
+                let mut fields = data.splitn(4, ',');
+                match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                    (Some(address), Some(workspace), Some(class), Some(title)) => Some(Self::OpenWindow {
+                        address: address.to_string(),
+                        workspace: workspace.to_string(),
+                        class: class.to_string(),
+                        title: title.to_string(),
+                    }),
+                    _ => None,
+                }
+            }
+            "closewindow" => Some(Self::CloseWindow { address: data.to_string() }),
+            "movewindow" => data.split_once(',').map(|(address, workspace)| Self::MoveWindow {
+                address: address.to_string(),
+                workspace: workspace.to_string(),
+            }),
+            "focusedmon" => data.split_once(',').map(|(monitor, workspace)| Self::FocusedMonitor {
+                monitor: monitor.to_string(),
+                workspace: workspace.to_string(),
+            }),
+            "activewindow" => data.split_once(',').map(|(class, title)| Self::ActiveWindow {
+                class: class.to_string(),
+                title: title.to_string(),
+            }),
+            "urgent" => Some(Self::Urgent { address: data.to_string() }),
+            "monitoradded" => Some(Self::MonitorAdded { name: data.to_string() }),
+            "monitorremoved" => Some(Self::MonitorRemoved { name: data.to_string() }),
+            "createworkspace" => Some(Self::CreateWorkspace { name: data.to_string() }),
+            "destroyworkspace" => Some(Self::DestroyWorkspace { name: data.to_string() }),
+            "configreloaded" => Some(Self::ConfigReloaded),
+            _ => None,
+        };
+
+        parsed.unwrap_or_else(|| Self::Unknown {
+            name: name.to_string(),
+            data: data.to_string(),
+        })
+    }
+}
+
+/// Default deadline for each Hyprland socket operation
+pub const DEFAULT_IPC_TIMEOUT_MS: u64 = 2000;
+
 /// Hyprland IPC client
 #[derive(Debug)]
 pub struct HyprlandIPC {
     socket_path: PathBuf,
     event_socket_path: PathBuf,
-    retry_max: u32,
-    retry_base_delay_ms: u64,
+    ipc_timeout_ms: u64,
 }
 
 impl HyprlandIPC {
     pub async fn new() -> Result<Self> {
-        Self::with_config(10, 500).await
+        Self::with_config(&ModuleConfig::default()).await
     }
 
-    pub async fn with_config(retry_max: u32, retry_base_delay_ms: u64) -> Result<Self> {
-        let instance_signature = env::var("HYPRLAND_INSTANCE_SIGNATURE")
-            .map_err(|_| anyhow!("HYPRLAND_INSTANCE_SIGNATURE not set"))?;
+    /// Connect using the retry, timeout and instance settings from the module configuration
+    pub async fn with_config(config: &ModuleConfig) -> Result<Self> {
+        let runtime_dir = PathBuf::from(
+            env::var("XDG_RUNTIME_DIR").map_err(|_| anyhow!("XDG_RUNTIME_DIR not set"))?,
+        );
+        let from_env = env::var("HYPRLAND_INSTANCE_SIGNATURE").ok();
+        Self::in_runtime_dir(config, &runtime_dir, from_env.as_deref()).await
+    }
 
-        // Validate instance signature
-        validate_instance_signature(&instance_signature)?;
+    /// Connect to an instance below `runtime_dir`, given the environment's signature if any
+    async fn in_runtime_dir(config: &ModuleConfig, runtime_dir: &Path, from_env: Option<&str>) -> Result<Self> {
+        let instance_signature = resolve_instance_signature(
+            runtime_dir,
+            config.instance_signature.as_deref(),
+            from_env,
+            config.ipc_timeout_ms,
+        )
+        .await?;
 
-        let runtime_dir = env::var("XDG_RUNTIME_DIR")
-            .map_err(|_| anyhow!("XDG_RUNTIME_DIR not set"))?;
-        
-        let socket_path = PathBuf::from(runtime_dir.clone())
-            .join("hypr")
-            .join(&instance_signature)
-            .join(".socket.sock");
-        
-        let event_socket_path = PathBuf::from(runtime_dir)
-            .join("hypr")
-            .join(&instance_signature)
-            .join(".socket2.sock");
+        let dir = instance_dir(runtime_dir, &instance_signature);
+        let socket_path = dir.join(".socket.sock");
+        let event_socket_path = dir.join(".socket2.sock");
         
         if !socket_path.exists() {
             return Err(anyhow!("Hyprland command socket not found: {:?}", socket_path));
@@ -78,95 +386,280 @@ impl HyprlandIPC {
         Ok(Self {
             socket_path,
             event_socket_path,
-            retry_max,
-            retry_base_delay_ms,
+            ipc_timeout_ms: config.ipc_timeout_ms,
         })
     }
     
-    pub async fn listen_for_events(&mut self) -> Result<String> {
-        let mut retry_count = 0;
-        let max_retries = self.retry_max;
-        let base_delay_ms = self.retry_base_delay_ms;
-        const MAX_DELAY_MS: u64 = 30000; // 30 seconds max
+    /// Client for a known socket, bypassing environment discovery
+    #[cfg(test)]
+    pub(crate) fn with_socket_path(socket_path: PathBuf, ipc_timeout_ms: u64) -> Self {
+        Self {
+            event_socket_path: socket_path.clone(),
+            socket_path,
+            ipc_timeout_ms,
+        }
+    }
 
-        loop {
-            match self.try_listen_for_events().await {
-                Ok(event) => {
-                    return Ok(event);
-                }
-                Err(e) => {
-                    retry_count += 1;
+    /// Open a long-lived stream of raw events from the event socket
+    pub fn event_stream(&self) -> EventStream {
+        EventStream::new(self.event_socket_path.clone(), self.ipc_timeout_ms)
+    }
+
+    pub async fn get_virtual_desktop_state(&self) -> Result<String> {
+        self.query("j/printstate").await
+    }
+    
+    pub async fn get_virtual_desktop_info(&self, vdesk_id: u32) -> Result<String> {
+        let command = format!("printdesk {}", vdesk_id);
+        self.send_command(&command).await
+    }
+    
+    /// List all windows as JSON (`clients`)
+    pub async fn get_clients(&self) -> Result<String> {
+        self.query("j/clients").await
+    }
+
+    /// Print the plugin's desktop layout as JSON (`printlayout`)
+    pub async fn print_layout(&self) -> Result<String> {
+        self.query("j/printlayout").await
+    }
+
+    /// Switch to a virtual desktop (`vdesk`)
+    pub async fn switch_to_virtual_desktop(&self, vdesk_id: u32) -> Result<()> {
+        validate_vdesk_id(vdesk_id)?;
+        self.dispatch("vdesk", Some(&vdesk_id.to_string())).await
+    }
 
-                    if retry_count > max_retries {
-                        log::error!("Max retries ({}) exceeded for event listening. Giving up.", max_retries);
-                        return Err(anyhow::anyhow!("Event listening failed after {} retries: {}", max_retries, e));
-                    }
+    /// Switch to a virtual desktop by its name (`vdesk <name>`)
+    pub async fn switch_to_virtual_desktop_by_name(&self, name: &str) -> Result<()> {
+        validate_vdesk_name(name)?;
+        self.dispatch("vdesk", Some(name)).await
+    }
 
-                    // Exponential backoff with jitter
-                    let base_delay = std::cmp::min(
-                        base_delay_ms * 2_u64.pow(retry_count - 1),
-                        MAX_DELAY_MS
-                    );
+    /// Switch to the previous virtual desktop (`prevdesk`)
+    pub async fn prev_desk(&self) -> Result<()> {
+        self.dispatch("prevdesk", None).await
+    }
 
-                    // Add ±25% jitter
-                    let jitter_range = base_delay / 4; // 25% of base delay
-                    let jitter = fastrand::u64(0..=jitter_range * 2); // 0 to 50% of base
-                    let delay_ms = base_delay.saturating_sub(jitter_range).saturating_add(jitter);
+    /// Switch to the next virtual desktop, creating it if needed (`nextdesk`)
+    pub async fn next_desk(&self) -> Result<()> {
+        self.dispatch("nextdesk", None).await
+    }
 
-                    log::warn!("Event listening failed (attempt {}/{}), retrying in {}ms (base: {}ms): {}",
-                              retry_count, max_retries, delay_ms, base_delay, e);
+    /// Cycle backwards through populated virtual desktops (`backcyclevdesks`)
+    pub async fn back_cycle_desks(&self) -> Result<()> {
+        self.dispatch("backcyclevdesks", None).await
+    }
 
-                    tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-                }
-            }
-        }
+    /// Cycle forwards through populated virtual desktops (`cyclevdesks`)
+    pub async fn cycle_desks(&self) -> Result<()> {
+        self.dispatch("cyclevdesks", None).await
+    }
+
+    /// Switch to the previously focused virtual desktop (`lastdesk`)
+    pub async fn last_desk(&self) -> Result<()> {
+        self.dispatch("lastdesk", None).await
+    }
+
+    /// Move the active window to a virtual desktop and follow it (`movetodesk`)
+    pub async fn move_to_desk(&self, vdesk_id: u32) -> Result<()> {
+        validate_vdesk_id(vdesk_id)?;
+        self.dispatch("movetodesk", Some(&vdesk_id.to_string())).await
+    }
+
+    /// Move the active window to a virtual desktop without switching (`movetodesksilent`)
+    pub async fn move_to_desk_silent(&self, vdesk_id: u32) -> Result<()> {
+        validate_vdesk_id(vdesk_id)?;
+        self.dispatch("movetodesksilent", Some(&vdesk_id.to_string())).await
     }
 
-    async fn try_listen_for_events(&mut self) -> Result<String> {
-        let stream = UnixStream::connect(&self.event_socket_path).await?;
-        let reader = BufReader::new(stream);
-        let mut lines = reader.lines();
+    /// Move the active window to the previous virtual desktop (`movetoprevdesk`)
+    pub async fn move_to_prev_desk(&self) -> Result<()> {
+        self.dispatch("movetoprevdesk", None).await
+    }
+
+    /// Move the active window to the next virtual desktop (`movetonextdesk`)
+    pub async fn move_to_next_desk(&self) -> Result<()> {
+        self.dispatch("movetonextdesk", None).await
+    }
 
-        while let Some(line) = lines.next_line().await? {
-            log::debug!("Received event: {}", line);
-            if line.starts_with("vdesk>>") {
-                return Ok(line);
+    /// Reset all virtual desktops, or only the given one (`vdeskreset`)
+    pub async fn reset_desks(&self, vdesk_id: Option<u32>) -> Result<()> {
+        match vdesk_id {
+            Some(vdesk_id) => {
+                validate_vdesk_id(vdesk_id)?;
+                self.dispatch("vdeskreset", Some(&vdesk_id.to_string())).await
             }
+            None => self.dispatch("vdeskreset", None).await,
         }
-
-        Err(anyhow!("Event stream ended"))
     }
-    
-    pub async fn get_virtual_desktop_state(&self) -> Result<String> {
-        self.send_command("j/printstate").await
+
+    /// Set a Hyprland config keyword at runtime and check Hyprland's response
+    pub async fn set_keyword(&self, keyword: &str, value: &str) -> Result<()> {
+        let command = format!("keyword {} {}", keyword, value);
+        let response = self.send_command(&command).await?;
+        check_dispatch_response(&command, &response)
     }
-    
-    pub async fn get_virtual_desktop_info(&self, vdesk_id: u32) -> Result<String> {
-        let command = format!("printdesk {}", vdesk_id);
-        self.send_command(&command).await
+
+    /// Run a JSON query and check that Hyprland answered with JSON
+    async fn query(&self, command: &str) -> Result<String> {
+        let response = self.send_command(command).await?;
+        check_query_response(command, response)
     }
-    
-    pub async fn switch_to_virtual_desktop(&self, vdesk_id: u32) -> Result<()> {
-        let command = format!("dispatch vdesk {}", vdesk_id);
-        self.send_command(&command).await?;
-        Ok(())
+
+    /// Run a dispatcher and check Hyprland's response
+    async fn dispatch(&self, dispatcher: &str, args: Option<&str>) -> Result<()> {
+        let command = match args {
+            Some(args) => format!("dispatch {} {}", dispatcher, args),
+            None => format!("dispatch {}", dispatcher),
+        };
+        let response = self.send_command(&command).await?;
+        check_dispatch_response(&command, &response)
     }
-    
+
     /// Send a raw command to Hyprland via the command socket
     pub async fn send_command(&self, command: &str) -> Result<String> {
         use tokio::io::{AsyncWriteExt, AsyncReadExt};
 
-        let mut stream = UnixStream::connect(&self.socket_path).await?;
+        let mut stream = with_timeout(
+            &format!("connect to command socket for '{}'", command),
+            self.ipc_timeout_ms,
+            UnixStream::connect(&self.socket_path),
+        )
+        .await?;
 
-        stream.write_all(command.as_bytes()).await?;
+        with_timeout(
+            &format!("write '{}'", command),
+            self.ipc_timeout_ms,
+            stream.write_all(command.as_bytes()),
+        )
+        .await?;
 
         let mut response = Vec::new();
-        stream.read_to_end(&mut response).await?;
+        with_timeout(
+            &format!("read response to '{}'", command),
+            self.ipc_timeout_ms,
+            stream.read_to_end(&mut response),
+        )
+        .await?;
 
         Ok(String::from_utf8_lossy(&response).to_string())
     }
 }
 
+/// Run a socket operation with a deadline, mapping expiry to a timeout error
+async fn with_timeout<T>(
+    operation: &str,
+    timeout_ms: u64,
+    future: impl std::future::Future<Output = std::io::Result<T>>,
+) -> Result<T> {
+    match tokio::time::timeout(tokio::time::Duration::from_millis(timeout_ms), future).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(VirtualDesktopError::timeout(operation, timeout_ms).into()),
+    }
+}
+
+/// Persistent connection to the Hyprland event socket
+///
+/// Stays connected across events. Once the socket is closed or a read fails it
+/// reconnects once, right away; if that fails the error is returned so the monitor
+/// loop can back off, report the outage and rediscover the instance.
+struct EventConnection {
+    event_socket_path: PathBuf,
+    ipc_timeout_ms: u64,
+    lines: Option<Lines<BufReader<UnixStream>>>,
+}
+
+impl EventConnection {
+    /// Read the next event line, reconnecting if the socket has closed
+    async fn next_line(&mut self) -> Result<String> {
+        loop {
+            let lines = match self.lines.as_mut() {
+                Some(lines) => lines,
+                None => self.lines.insert(self.connect().await?),
+            };
+
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    log::debug!("Received event: {}", line);
+                    return Ok(line);
+                }
+                Ok(None) => {
+                    log::warn!("Hyprland event socket closed, reconnecting");
+                    self.lines = None;
+                }
+                Err(e) => {
+                    log::warn!("Failed to read from Hyprland event socket, reconnecting: {}", e);
+                    self.lines = None;
+                }
+            }
+        }
+    }
+
+    /// Connect to the event socket with a single attempt
+    ///
+    /// A crashed compositor can leave its socket file behind, so a refused connection
+    /// is reported straight away instead of being retried here.
+    async fn connect(&self) -> Result<Lines<BufReader<UnixStream>>> {
+        // The compositor removes its sockets on exit; retrying a vanished path only delays rediscovery
+        if !self.event_socket_path.exists() {
+            return Err(anyhow::anyhow!(
+                "Hyprland event socket {:?} no longer exists",
+                self.event_socket_path
+            ));
+        }
+
+        let connect = with_timeout(
+            "connect to event socket",
+            self.ipc_timeout_ms,
+            UnixStream::connect(&self.event_socket_path),
+        );
+        let stream = connect.await.map_err(|e| {
+            anyhow::anyhow!("Failed to connect to Hyprland event socket {:?}: {}", self.event_socket_path, e)
+        })?;
+        log::debug!("Connected to Hyprland event socket {:?}", self.event_socket_path);
+        Ok(BufReader::new(stream).lines())
+    }
+}
+
+/// Async stream of raw event lines from the Hyprland event socket
+///
+/// Yields every line received. An error is yielded once the socket cannot be
+/// reconnected, after which the stream ends.
+pub struct EventStream {
+    inner: Pin<Box<dyn Stream<Item = Result<String>> + Send>>,
+}
+
+impl EventStream {
+    fn new(event_socket_path: PathBuf, ipc_timeout_ms: u64) -> Self {
+        let connection = EventConnection {
+            event_socket_path,
+            ipc_timeout_ms,
+            lines: None,
+        };
+
+        let inner = stream::unfold(Some(connection), |connection| async move {
+            let mut connection = connection?;
+            match connection.next_line().await {
+                Ok(line) => Some((Ok(line), Some(connection))),
+                Err(e) => Some((Err(e), None)),
+            }
+        });
+
+        Self {
+            inner: Box::pin(inner.fuse()),
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -184,46 +677,375 @@ mod tests {
 
     #[tokio::test]
     async fn test_environment_variable_validation() {
-        std::env::set_var("XDG_RUNTIME_DIR", "/tmp");
+        let runtime_dir = tempfile::tempdir().unwrap();
+        let connect = |signature: String| {
+            let runtime_dir = runtime_dir.path().to_path_buf();
+            async move {
+                HyprlandIPC::in_runtime_dir(&ModuleConfig::default(), &runtime_dir, Some(&signature)).await
+            }
+        };
 
-        std::env::set_var("HYPRLAND_INSTANCE_SIGNATURE", "../malicious");
-        let result = HyprlandIPC::new().await;
+        let result = connect("../malicious".to_string()).await;
         assert!(result.is_err(), "Should reject path traversal attempts");
         if let Err(e) = result {
             assert!(e.to_string().contains("unsafe characters"));
         }
 
-        std::env::set_var("HYPRLAND_INSTANCE_SIGNATURE", "");
-        let result = HyprlandIPC::new().await;
+        let result = connect(String::new()).await;
         assert!(result.is_err(), "Should reject empty signature");
         if let Err(e) = result {
             assert!(e.to_string().contains("empty signature"));
         }
 
-        std::env::set_var("HYPRLAND_INSTANCE_SIGNATURE", "test/malicious");
-        let result = HyprlandIPC::new().await;
+        let result = connect("test/malicious".to_string()).await;
         assert!(result.is_err(), "Should reject signature with slash");
         if let Err(e) = result {
             assert!(e.to_string().contains("unsafe characters"));
         }
 
-        std::env::set_var("HYPRLAND_INSTANCE_SIGNATURE", "test$malicious");
-        let result = HyprlandIPC::new().await;
+        let result = connect("test$malicious".to_string()).await;
         assert!(result.is_err(), "Should reject signature with special characters");
 
-        std::env::set_var("HYPRLAND_INSTANCE_SIGNATURE", &"a".repeat(65));
-        let result = HyprlandIPC::new().await;
+        let result = connect("a".repeat(65)).await;
         assert!(result.is_err(), "Should reject signature that's too long");
 
         for valid_sig in &["test123", "hypr_instance", "session-1", "a", "A1_-test"] {
-            std::env::set_var("HYPRLAND_INSTANCE_SIGNATURE", valid_sig);
-            let result = HyprlandIPC::new().await;
+            let result = connect(valid_sig.to_string()).await;
             if let Err(e) = result {
                 assert!(e.to_string().contains("socket not found") || e.to_string().contains("No such file"));
             }
         }
     }
 
+    #[tokio::test]
+    async fn test_event_stream_keeps_connection_and_reconnects() {
+        use futures::StreamExt;
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("events.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let server = tokio::spawn(async move {
+            // First connection delivers a burst of events, then closes
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"vdesk>>2\nworkspace>>3\nvdesk>>3\n")
+                .await
+                .unwrap();
+            drop(stream);
+
+            // Second connection is only made after the socket closed
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(b"vdesk>>1\n").await.unwrap();
+        });
+
+        let mut events = EventStream::new(socket_path.clone(), DEFAULT_IPC_TIMEOUT_MS);
+        assert_eq!(events.next().await.unwrap().unwrap(), "vdesk>>2");
+        assert_eq!(events.next().await.unwrap().unwrap(), "workspace>>3");
+        assert_eq!(events.next().await.unwrap().unwrap(), "vdesk>>3");
+        assert_eq!(events.next().await.unwrap().unwrap(), "vdesk>>1");
+
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_dispatch_argument_validation() {
+        assert!(validate_vdesk_id(1).is_ok());
+        assert!(validate_vdesk_id(42).is_ok());
+        assert!(validate_vdesk_id(0).is_err());
+
+        assert!(validate_vdesk_name("Research").is_ok());
+        assert!(validate_vdesk_name("  Focus").is_ok());
+        assert!(validate_vdesk_name(" ").is_err());
+        assert!(validate_vdesk_name("Comms\ndispatch exit").is_err());
+        assert!(validate_vdesk_name("3").is_err());
+    }
+
+    #[test]
+    fn test_dispatch_response_check() {
+        assert!(check_dispatch_response("dispatch vdesk 1", "ok").is_ok());
+        assert!(check_dispatch_response("dispatch vdesk 1", "ok\n").is_ok());
+
+        let err = check_dispatch_response("dispatch vdesk 1", "").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VirtualDesktopError>(),
+            Some(VirtualDesktopError::EmptyResponse { command }) if command == "dispatch vdesk 1"
+        ));
+
+        let err = check_dispatch_response("dispatch nextdesk", "Invalid dispatcher").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VirtualDesktopError>(),
+            Some(VirtualDesktopError::CommandRejected { command, response })
+                if command == "dispatch nextdesk" && response == "Invalid dispatcher"
+        ));
+    }
+
+    #[test]
+    fn test_command_response_classification() {
+        assert_eq!(CommandResponse::classify("ok"), CommandResponse::Ok);
+        assert_eq!(CommandResponse::classify(" ok\n"), CommandResponse::Ok);
+        assert_eq!(CommandResponse::classify(""), CommandResponse::Empty);
+        assert_eq!(
+            CommandResponse::classify("unknown request"),
+            CommandResponse::Error("unknown request".to_string())
+        );
+    }
+
+    #[test]
+    fn test_query_response_check() {
+        let state = r#"[{"id": 1}]"#.to_string();
+        assert_eq!(check_query_response("j/printstate", state.clone()).unwrap(), state);
+
+        // Plugin not loaded: Hyprland answers with error text instead of JSON
+        let err = check_query_response("j/printstate", "unknown request".to_string()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VirtualDesktopError>(),
+            Some(VirtualDesktopError::CommandRejected { command, .. }) if command == "j/printstate"
+        ));
+
+        let err = check_query_response("j/printstate", String::new()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VirtualDesktopError>(),
+            Some(VirtualDesktopError::EmptyResponse { .. })
+        ));
+    }
+
+    /// Fake runtime dir with one Hyprland instance per signature; listeners keep instances alive
+    ///
+    /// Instances are started one second apart in the order given, live ones first.
+    fn fake_instances(live: &[&str], dead: &[&str]) -> (tempfile::TempDir, Vec<tokio::net::UnixListener>) {
+        let runtime_dir = tempfile::tempdir().unwrap();
+
+        let mut listeners = Vec::new();
+        for (started, signature) in live.iter().chain(dead).enumerate() {
+            let dir = instance_dir(runtime_dir.path(), signature);
+            std::fs::create_dir_all(&dir).unwrap();
+            let command = tokio::net::UnixListener::bind(dir.join(".socket.sock")).unwrap();
+            let _event = std::os::unix::net::UnixListener::bind(dir.join(".socket2.sock")).unwrap();
+            if live.contains(signature) {
+                listeners.push(command);
+            }
+            // Sockets of dead instances stay on disk but refuse connections once dropped
+            let mtime = filetime::FileTime::from_unix_time(1_700_000_000 + started as i64, 0);
+            // Sockets cannot be opened, so set the times by path
+            filetime::set_symlink_file_times(dir.join(".socket.sock"), mtime, mtime).unwrap();
+        }
+        (runtime_dir, listeners)
+    }
+
+    #[tokio::test]
+    async fn test_instance_discovery() {
+        let (runtime_dir, _listeners) = fake_instances(&["old_live"], &["newer_dead"]);
+        let runtime_dir = runtime_dir.path();
+
+        // Missing signature: the only responsive instance is chosen
+        let found = resolve_instance_signature(runtime_dir, None, None, 200).await.unwrap();
+        assert_eq!(found, "old_live");
+
+        // Stale signature from a previous session falls back to discovery
+        let found = resolve_instance_signature(runtime_dir, None, Some("newer_dead"), 200).await.unwrap();
+        assert_eq!(found, "old_live");
+
+        // A live environment signature is used directly
+        let found = resolve_instance_signature(runtime_dir, None, Some("old_live"), 200).await.unwrap();
+        assert_eq!(found, "old_live");
+
+        // The configured override wins and is still validated
+        let found = resolve_instance_signature(runtime_dir, Some("newer_dead"), Some("old_live"), 200).await.unwrap();
+        assert_eq!(found, "newer_dead");
+        assert!(resolve_instance_signature(runtime_dir, Some("../escape"), None, 200).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_instance_discovery_prefers_newest_and_skips_invalid_names() {
+        let (runtime_dir, _listeners) = fake_instances(&["first", "second"], &[]);
+        std::fs::create_dir_all(runtime_dir.path().join("hypr").join("not valid!")).unwrap();
+
+        let found = resolve_instance_signature(runtime_dir.path(), None, None, 200).await.unwrap();
+        assert_eq!(found, "second");
+
+        // Nothing running at all
+        let empty = tempfile::tempdir().unwrap();
+        let err = resolve_instance_signature(empty.path(), None, None, 200).await.unwrap_err();
+        assert!(err.to_string().contains("no running Hyprland instance"));
+    }
+
+    #[tokio::test]
+    async fn test_send_command_times_out_on_unresponsive_socket() {
+        use tokio::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("command.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        // Accept the connection but never answer, like a frozen compositor
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            drop(stream);
+        });
+
+        let ipc = HyprlandIPC::with_socket_path(socket_path.clone(), 50);
+
+        let err = ipc.send_command("j/printstate").await.unwrap_err();
+        match err.downcast_ref::<VirtualDesktopError>() {
+            Some(e @ VirtualDesktopError::Timeout { operation, timeout_ms }) => {
+                assert!(operation.contains("j/printstate"));
+                assert_eq!(*timeout_ms, 50);
+                assert_eq!(e.severity(), crate::errors::ErrorSeverity::Recoverable);
+            }
+            other => panic!("Expected Timeout error, got {:?}", other),
+        }
+
+        server.abort();
+    }
+
+    #[test]
+    fn test_connection_error_classification() {
+        let io = anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        assert!(is_connection_error(&io));
+
+        let timeout = anyhow::Error::from(VirtualDesktopError::timeout("connect", 100));
+        assert!(is_connection_error(&timeout));
+
+        let rejected = anyhow::Error::from(VirtualDesktopError::command_rejected("dispatch vdesk 1", "error"));
+        assert!(!is_connection_error(&rejected));
+        assert!(!is_connection_error(&anyhow!("Failed to parse virtual desktop JSON")));
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_fails_fast_when_socket_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("removed.sock");
+
+        let mut events = EventStream::new(socket_path, DEFAULT_IPC_TIMEOUT_MS);
+        let next = tokio::time::timeout(tokio::time::Duration::from_secs(1), events.next())
+            .await
+            .expect("stream should fail without retrying");
+        assert!(next.unwrap().unwrap_err().to_string().contains("no longer exists"));
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_fails_fast_when_compositor_crashed() {
+        use futures::StreamExt;
+        use tokio::io::AsyncWriteExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("crashed.sock");
+        let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+
+        let mut events = EventStream::new(socket_path.clone(), DEFAULT_IPC_TIMEOUT_MS);
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(b"vdesk>>2\n").await.unwrap();
+            // Dropping the listener without unlinking leaves the socket file behind, like a crash
+        });
+        assert_eq!(events.next().await.unwrap().unwrap(), "vdesk>>2");
+        server.await.unwrap();
+        assert!(socket_path.exists());
+
+        // The monitor loop handles backoff and rediscovery, so the stream must not retry on its own
+        let next = tokio::time::timeout(tokio::time::Duration::from_secs(1), events.next())
+            .await
+            .expect("stream should fail without retrying");
+        assert!(next.unwrap().unwrap_err().to_string().contains("Failed to connect"));
+        assert!(events.next().await.is_none());
+    }
+
+    #[test]
+    fn test_hyprland_event_parsing() {
+        assert_eq!(HyprlandEvent::parse("vdesk>>2"), HyprlandEvent::VirtualDesktop { id: 2 });
+        assert_eq!(
+            HyprlandEvent::parse("workspace>>3"),
+            HyprlandEvent::Workspace { name: "3".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("workspacev2>>-98,special:scratch"),
+            HyprlandEvent::WorkspaceV2 { id: -98, name: "special:scratch".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("openwindow>>80e62df0,2,kitty,vim a, b"),
+            HyprlandEvent::OpenWindow {
+                address: "80e62df0".to_string(),
+                workspace: "2".to_string(),
+                class: "kitty".to_string(),
+                title: "vim a, b".to_string(),
+            }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("closewindow>>80e62df0"),
+            HyprlandEvent::CloseWindow { address: "80e62df0".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("movewindow>>80e62df0,4"),
+            HyprlandEvent::MoveWindow { address: "80e62df0".to_string(), workspace: "4".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("focusedmon>>DP-1,2"),
+            HyprlandEvent::FocusedMonitor { monitor: "DP-1".to_string(), workspace: "2".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("activewindow>>firefox,Title, with comma"),
+            HyprlandEvent::ActiveWindow { class: "firefox".to_string(), title: "Title, with comma".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("urgent>>80e62df0"),
+            HyprlandEvent::Urgent { address: "80e62df0".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("monitoradded>>HDMI-A-1"),
+            HyprlandEvent::MonitorAdded { name: "HDMI-A-1".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("monitorremoved>>HDMI-A-1"),
+            HyprlandEvent::MonitorRemoved { name: "HDMI-A-1".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("createworkspace>>5"),
+            HyprlandEvent::CreateWorkspace { name: "5".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("destroyworkspace>>5"),
+            HyprlandEvent::DestroyWorkspace { name: "5".to_string() }
+        );
+        assert_eq!(HyprlandEvent::parse("configreloaded>>"), HyprlandEvent::ConfigReloaded);
+
+        // Unknown events and malformed data fall back to Unknown
+        assert_eq!(
+            HyprlandEvent::parse("fullscreen>>1"),
+            HyprlandEvent::Unknown { name: "fullscreen".to_string(), data: "1".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("vdesk>>abc"),
+            HyprlandEvent::Unknown { name: "vdesk".to_string(), data: "abc".to_string() }
+        );
+        assert_eq!(
+            HyprlandEvent::parse("garbage"),
+            HyprlandEvent::Unknown { name: "garbage".to_string(), data: String::new() }
+        );
+    }
+
+    #[test]
+    fn test_events_affecting_virtual_desktops() {
+        for line in [
+            "vdesk>>2",
+            "openwindow>>80e62df0,2,kitty,vim",
+            "closewindow>>80e62df0",
+            "movewindow>>80e62df0,4",
+            "createworkspace>>5",
+            "destroyworkspace>>5",
+            "urgent>>80e62df0",
+        ] {
+            assert!(HyprlandEvent::parse(line).affects_virtual_desktops(), "{line}");
+        }
+
+        for line in ["workspace>>3", "activewindow>>kitty,vim", "focusedmon>>DP-1,2", "fullscreen>>1"] {
+            assert!(!HyprlandEvent::parse(line).affects_virtual_desktops(), "{line}");
+        }
+    }
+
     #[test]
     fn test_instance_signature_validation() {
         assert!(validate_instance_signature("test123").is_ok());
@@ -4,108 +4,109 @@
 use std::sync::Arc;
 use std::thread;
 
-use anyhow::Result;
 use serde::Deserialize;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use waybar_cffi::{
-    gtk::{prelude::*, Box as GtkBox, Orientation, glib},
+    gtk::{gdk, prelude::*, Box as GtkBox, EventBox, Orientation, glib},
     waybar_module, InitInfo, Module,
 };
 
+pub mod actions;
+pub mod commands;
 pub mod config;
 pub mod errors;
+pub mod health;
 pub mod hyprland;
 pub mod metrics;
 pub mod monitor;
+pub mod reload;
+pub mod signals;
 pub mod ui;
 pub mod vdesk;
 
+use actions::Action;
+use commands::{CommandQueue, DesktopCommand};
 use config::ModuleConfig;
-use hyprland::HyprlandIPC;
+use health::{HealthReport, HealthState};
+use hyprland::is_connection_error;
 use metrics::PerformanceMetrics;
-use ui::WidgetManager;
+use monitor::MonitorSupervisor;
+use reload::ConfigSource;
+use signals::SignalAction;
+use ui::{ScrollAccumulator, ScrollDirection, WidgetManager};
 use vdesk::VirtualDesktopsManager;
 
 type VdeskUpdateMessage = Vec<vdesk::VirtualDesktop>;
 
 /// Configuration wrapper supporting both nested and direct formats
+///
+/// Options are kept as raw JSON until [`ConfigWrapper::into_config`] so that one
+/// invalid value cannot fail the whole block.
 #[derive(Deserialize)]
 #[serde(untagged)]
 pub enum ConfigWrapper {
-    Nested { config: ModuleConfig },
-    Direct(ModuleConfig),
+    Nested { config: serde_json::Map<String, serde_json::Value> },
+    Direct(serde_json::Map<String, serde_json::Value>),
 }
 
-impl From<ConfigWrapper> for ModuleConfig {
-    fn from(wrapper: ConfigWrapper) -> Self {
-        match wrapper {
-            ConfigWrapper::Direct(config) => config,
+impl ConfigWrapper {
+    /// The module's options as given by Waybar
+    pub fn into_options(self) -> serde_json::Map<String, serde_json::Value> {
+        match self {
+            ConfigWrapper::Direct(map) => map,
             ConfigWrapper::Nested { config } => config,
         }
     }
-}
-
-/// Waybar module configuration interface
-#[derive(Deserialize)]
-pub struct Config {
-    #[serde(default = "default_format")]
-    pub format: String,
-    #[serde(default = "default_show_empty")]
-    pub show_empty: bool,
-    #[serde(default = "default_separator")]
-    pub separator: String,
-    #[serde(default)]
-    pub format_icons: std::collections::HashMap<String, String>,
-    #[serde(default = "default_show_window_count")]
-    pub show_window_count: bool,
-    #[serde(default = "default_sort_by")]
-    pub sort_by: String,
-    #[serde(default = "default_retry_max")]
-    pub retry_max: u32,
-    #[serde(default = "default_retry_base_delay_ms")]
-    pub retry_base_delay_ms: u64,
-}
-
-fn default_format() -> String {
-    "{name}".to_string()
-}
-
-fn default_show_empty() -> bool {
-    false
-}
 
-fn default_separator() -> String {
-    " ".to_string()
-}
-
-fn default_show_window_count() -> bool {
-    false
+    /// Parse the module options, returning any that fell back to their defaults
+    pub fn into_config(self) -> (ModuleConfig, Vec<errors::VirtualDesktopError>) {
+        ModuleConfig::from_json_map(self.into_options())
+    }
 }
 
-fn default_sort_by() -> String {
-    "number".to_string()
-}
+/// Log unknown keys and invalid settings, returning the messages for the bar
+///
+/// Never panic here: with panic = "abort" that would take down all of Waybar.
+fn report_config_problems(config: &ModuleConfig, errors: &[errors::VirtualDesktopError]) -> Vec<String> {
+    let unknown_keys = config::find_unknown_keys(config.unknown_keys.keys().map(String::as_str));
+    if !unknown_keys.is_empty() {
+        let described: Vec<String> = unknown_keys.iter().map(|key| key.to_string()).collect();
+        log::warn!("Ignoring unknown configuration keys: {}", described.join(", "));
+    }
 
-fn default_retry_max() -> u32 {
-    10
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    if !messages.is_empty() {
+        log::error!(
+            "Invalid configuration, using defaults for {} setting(s):\n  {}",
+            messages.len(),
+            messages.join("\n  ")
+        );
+    }
+    messages
 }
 
-fn default_retry_base_delay_ms() -> u64 {
-    500
+impl From<ConfigWrapper> for ModuleConfig {
+    fn from(wrapper: ConfigWrapper) -> Self {
+        wrapper.into_config().0
+    }
 }
 
 /// Main Waybar module for Hyprland virtual desktop display
 pub struct VirtualDesktopsModule {
-    _widget_manager: Arc<std::sync::Mutex<WidgetManager>>,
+    widget_manager: Arc<std::sync::Mutex<WidgetManager>>,
     _runtime: Arc<tokio::runtime::Runtime>,
-    shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
-    monitor_handle: Option<tokio::task::JoinHandle<()>>,
+    commands: CommandQueue,
+    monitor: Arc<MonitorSupervisor>,
     metrics: Arc<PerformanceMetrics>,
     _update_handle: glib::JoinHandle<()>,
+    _command_results_handle: glib::JoinHandle<()>,
+    _health_handle: glib::JoinHandle<()>,
+    reload_tx: mpsc::UnboundedSender<()>,
+    _reload_handle: glib::JoinHandle<()>,
 }
 
 impl Module for VirtualDesktopsModule {
-    type Config = Config;
+    type Config = ConfigWrapper;
 
     fn init(info: &InitInfo, config: Self::Config) -> Self {
         let _ = env_logger::try_init();
@@ -114,31 +115,11 @@ impl Module for VirtualDesktopsModule {
         let _init_start = std::time::Instant::now();
         let metrics = Arc::new(PerformanceMetrics::new());
 
-        // Convert string sort_by to enum
-        let sort_by = config.sort_by.parse().unwrap_or_else(|e| {
-            log::warn!(
-                "Invalid sort_by value '{}': {}. Using default.",
-                config.sort_by,
-                e
-            );
-            crate::config::SortStrategy::default()
-        });
-
-        let module_config = ModuleConfig {
-            format: config.format,
-            show_empty: config.show_empty,
-            separator: config.separator,
-            format_icons: config.format_icons,
-            show_window_count: config.show_window_count,
-            sort_by,
-            retry_max: config.retry_max,
-            retry_base_delay_ms: config.retry_base_delay_ms,
-        };
-
-        if let Err(e) = module_config.validate() {
-            log::error!("Configuration validation failed: {}", e);
-            panic!("Invalid configuration: {}", e);
-        }
+        // Invalid values fall back to their defaults and are reported together below.
+        // Waybar's block is kept so the configuration can be reloaded later.
+        let source = ConfigSource::new(config.into_options());
+        let (module_config, config_errors) = source.load();
+        let config_error_messages = report_config_problems(&module_config, &config_errors);
 
         log::debug!(
             "Module config: format={}, show_empty={}",
@@ -151,28 +132,47 @@ impl Module for VirtualDesktopsModule {
         // Set CSS name for styling
         hbox.set_widget_name("waybar-vd");
 
-        container.add(&hbox);
+        // The box has no window of its own, so an event box receives scroll events for it
+        let event_box = EventBox::new();
+        event_box.add_events(gdk::EventMask::SCROLL_MASK | gdk::EventMask::SMOOTH_SCROLL_MASK);
+        event_box.add(&hbox);
+
+        container.add(&event_box);
         log::debug!("Created GTK container widget with CSS name 'waybar-vd'");
 
         let rt = Arc::new(tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime"));
         let runtime_handle = rt.handle().clone();
-        let manager = Arc::new(tokio::sync::Mutex::new(VirtualDesktopsManager::new()));
+        let manager = Arc::new(tokio::sync::Mutex::new(VirtualDesktopsManager::with_config(&module_config)));
 
-        let (tx, mut rx) = mpsc::channel::<VdeskUpdateMessage>(32);
+        // Latest-value channel: the UI only ever renders the newest snapshot and the
+        // monitor never blocks on a busy main loop
+        let (tx, mut rx) = watch::channel::<VdeskUpdateMessage>(Vec::new());
+        let (health_tx, mut health_rx) = watch::channel(HealthReport::default());
+        // Reloaded configurations reach the monitor and command worker through this channel
+        let (config_tx, config_rx) = watch::channel(module_config.clone());
 
         {
             let manager_for_init = Arc::clone(&manager);
             let tx_for_init = tx.clone();
+            let health_for_init = health_tx.clone();
+            let metrics_for_init = Arc::clone(&metrics);
             thread::spawn(move || {
                 runtime_handle.block_on(async {
                     let mut mgr = manager_for_init.lock().await;
                     if let Err(e) = mgr.initialize().await {
                         log::error!("Failed to initialize virtual desktop manager: {}", e);
+                        // Show a placeholder instead of an empty box until the monitor connects
+                        let state = if is_connection_error(&e) {
+                            HealthState::Disconnected
+                        } else {
+                            HealthState::Degraded
+                        };
+                        monitor::set_health(&health_for_init, &metrics_for_init, state, Some(e.to_string()));
                         return;
                     }
                     // Send the initial state immediately
                     let initial_state = mgr.get_virtual_desktops();
-                    if let Err(e) = tx_for_init.send(initial_state).await {
+                    if let Err(e) = tx_for_init.send(initial_state) {
                         log::error!("Failed to send initial state: {}", e);
                     }
                 });
@@ -183,21 +183,33 @@ impl Module for VirtualDesktopsModule {
             });
         }
 
-        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
-        let manager_clone = Arc::clone(&manager);
-        let config_clone = module_config.clone();
-        let monitor_handle = rt.handle().spawn(async move {
-            // Pass the transmitter `tx` to the monitor loop
-            if let Err(e) =
-                crate::monitor::resilient_monitor_loop(manager_clone, config_clone, shutdown_rx, tx)
-                    .await
-            {
-                log::error!("Resilient monitor loop failed: {e}");
-            }
-        });
-
-        let widget_manager =
-            WidgetManager::new(hbox, module_config.clone(), Arc::clone(&metrics));
+        // The supervisor can restart the monitor after it gives up, e.g. from a placeholder click
+        let monitor = Arc::new(MonitorSupervisor::new(
+            rt.handle().clone(),
+            Arc::clone(&manager),
+            config_rx.clone(),
+            Arc::clone(&metrics),
+            tx,
+            health_tx,
+        ));
+        monitor.start();
+
+        // Clicks, scrolling and actions share one worker and IPC client on the runtime
+        let (command_results_tx, mut command_results_rx) = mpsc::unbounded_channel();
+        let commands = CommandQueue::spawn(
+            rt.handle(),
+            config_rx,
+            Arc::clone(&manager),
+            Arc::clone(&metrics),
+            command_results_tx,
+        );
+        let widget_manager = WidgetManager::new(
+            hbox,
+            module_config.clone(),
+            Arc::clone(&metrics),
+            commands.clone(),
+            Arc::clone(&monitor),
+        );
         let metrics_clone = Arc::clone(&metrics);
 
         // Create a shared reference to the widget manager
@@ -205,9 +217,44 @@ impl Module for VirtualDesktopsModule {
         #[allow(clippy::arc_with_non_send_sync)]
         let widget_manager_shared = Arc::new(std::sync::Mutex::new(widget_manager));
         let widget_manager_clone = Arc::clone(&widget_manager_shared);
+        if let Ok(wm) = widget_manager_shared.lock() {
+            wm.set_config_errors(&config_error_messages);
+        }
+
+        let widget_manager_for_scroll = Arc::clone(&widget_manager_shared);
+        let scroll_accumulator =
+            std::cell::RefCell::new(ScrollAccumulator::new(module_config.scroll_threshold));
+        event_box.connect_scroll_event(move |_, event| {
+            let Ok(wm) = widget_manager_for_scroll.lock() else {
+                return glib::Propagation::Stop;
+            };
+            let direction = match event.direction() {
+                gdk::ScrollDirection::Up => Some(ScrollDirection::Up),
+                gdk::ScrollDirection::Down => Some(ScrollDirection::Down),
+                gdk::ScrollDirection::Smooth => {
+                    let mut accumulator = scroll_accumulator.borrow_mut();
+                    // The threshold may have changed since the last event after a reload
+                    accumulator.set_threshold(wm.config().scroll_threshold);
+                    if event.is_stop() {
+                        accumulator.reset();
+                        None
+                    } else {
+                        accumulator.accumulate(event.delta().1)
+                    }
+                }
+                _ => None,
+            };
+
+            if let Some(direction) = direction {
+                wm.handle_scroll(direction);
+            }
+            glib::Propagation::Stop
+        });
 
         let update_handle = glib::MainContext::default().spawn_local(async move {
-            while let Some(vdesks) = rx.recv().await {
+            while rx.changed().await.is_ok() {
+                // Intermediate states published while the UI was busy are skipped
+                let vdesks = rx.borrow_and_update().clone();
                 let _timer = metrics_clone.start_widget_update_timer(Arc::clone(&metrics_clone));
 
                 // This code now runs on the main UI thread without blocking
@@ -222,13 +269,68 @@ impl Module for VirtualDesktopsModule {
             }
         });
 
+        // Reflect the monitor's connection health, starting with its current state
+        let health_for_reload = health_rx.clone();
+        let widget_manager_for_health = Arc::clone(&widget_manager_shared);
+        let health_handle = glib::MainContext::default().spawn_local(async move {
+            loop {
+                let report = health_rx.borrow_and_update().clone();
+                if let Ok(wm) = widget_manager_for_health.lock() {
+                    wm.set_health(&report);
+                }
+                if health_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Reflect failed commands from clicks and scrolling in the bar
+        let widget_manager_for_results = Arc::clone(&widget_manager_shared);
+        let command_results_handle = glib::MainContext::default().spawn_local(async move {
+            while let Some(result) = command_results_rx.recv().await {
+                if let Ok(wm) = widget_manager_for_results.lock() {
+                    match result {
+                        Ok(()) => wm.clear_command_error(),
+                        Err(message) => wm.show_command_error(&message),
+                    }
+                }
+            }
+        });
+
+        // Reload on Waybar's refresh signal and, when configured, whenever the override file changes
+        let (reload_tx, mut reload_rx) = mpsc::unbounded_channel();
+        if let Some(path) = source.override_path() {
+            rt.handle().spawn(reload::watch_override_file(path, reload_tx.clone()));
+        }
+        let widget_manager_for_reload = Arc::clone(&widget_manager_shared);
+        let reload_handle = glib::MainContext::default().spawn_local(async move {
+            while reload_rx.recv().await.is_some() {
+                let (new_config, errors) = source.load();
+                let messages = report_config_problems(&new_config, &errors);
+                config_tx.send_replace(new_config.clone());
+                if let Ok(mut wm) = widget_manager_for_reload.lock() {
+                    if let Err(e) = wm.set_config(new_config) {
+                        log::error!("Failed to apply reloaded configuration: {e}");
+                    }
+                    wm.set_config_errors(&messages);
+                    // Placeholder labels come from the configuration too
+                    wm.set_health(&health_for_reload.borrow());
+                }
+                log::info!("Configuration reloaded");
+            }
+        });
+
         Self {
-            _widget_manager: widget_manager_shared,
+            widget_manager: widget_manager_shared,
             _runtime: rt,
-            shutdown_tx: Some(shutdown_tx),
-            monitor_handle: Some(monitor_handle),
+            commands,
+            monitor,
             metrics: Arc::clone(&metrics),
             _update_handle: update_handle, // Store the handle
+            _command_results_handle: command_results_handle,
+            _health_handle: health_handle,
+            reload_tx,
+            _reload_handle: reload_handle,
         }
     }
 
@@ -237,15 +339,25 @@ impl Module for VirtualDesktopsModule {
         log::debug!("Manual update triggered, but updates are now event-driven.");
     }
 
-    fn refresh(&mut self, _signal: i32) {
-        self.update();
+    fn refresh(&mut self, signal: i32) {
+        let action = match self.widget_manager.lock() {
+            Ok(wm) => signals::signal_action(wm.config(), signal),
+            Err(_) => return,
+        };
+        log::debug!("Refresh signal {} maps to {:?}", signal, action);
+
+        match action {
+            Some(SignalAction::Resync) => self.resync(),
+            Some(SignalAction::ReloadConfig) => self.reload_config(),
+            Some(SignalAction::DumpMetrics) => self.force_metrics_log(),
+            None => {}
+        }
     }
 
     fn do_action(&mut self, action: &str) {
-        if let Ok(vdesk_id) = action.parse::<u32>() {
-            if let Err(e) = self.switch_to_virtual_desktop(vdesk_id) {
-                log::error!("Failed to switch to virtual desktop {}: {}", vdesk_id, e);
-            }
+        match action.parse::<Action>() {
+            Ok(action) => self.run_action(&action),
+            Err(e) => log::error!("Ignoring action from Waybar: {}", e),
         }
     }
 }
@@ -254,11 +366,7 @@ impl Drop for VirtualDesktopsModule {
     fn drop(&mut self) {
         log::info!("VirtualDesktopsModule dropping - initiating graceful shutdown");
 
-        if let Some(shutdown_tx) = self.shutdown_tx.take() {
-            let _ = shutdown_tx.send(());
-        }
-
-        if let Some(monitor_handle) = self.monitor_handle.take() {
+        if let Some(monitor_handle) = self.monitor.stop() {
             let rt_handle = self._runtime.handle().clone();
             std::thread::spawn(move || {
                 rt_handle.block_on(async {
@@ -305,28 +413,41 @@ impl VirtualDesktopsModule {
         log::info!("=== END METRICS REPORT ===");
     }
 
-    fn switch_to_virtual_desktop(&self, vdesk_id: u32) -> Result<()> {
-        // Spawn a new thread to handle the async task
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap();
-            
-            rt.block_on(async move {
-                match HyprlandIPC::new().await {
-                    Ok(ipc) => {
-                        if let Err(e) = ipc.switch_to_virtual_desktop(vdesk_id).await {
-                            log::error!("Failed to switch to virtual desktop {}: {}", vdesk_id, e);
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("Failed to create Hyprland IPC for action: {}", e);
-                    }
-                }
-            });
-        });
-        Ok(())
+    /// Fetch the full state from Hyprland now, recovering from missed events
+    ///
+    /// While disconnected this skips the remaining backoff and reconnects instead, and
+    /// once monitoring has stopped it starts it again.
+    pub fn resync(&self) {
+        log::info!("Resync requested");
+        self.monitor.reconnect();
+    }
+
+    /// Re-read the configuration and apply it without restarting Waybar
+    pub fn reload_config(&self) {
+        if self.reload_tx.send(()).is_err() {
+            log::warn!("Configuration reload loop has stopped");
+        }
+    }
+
+    /// Carry out an action sent through Waybar's `actions` bindings
+    pub fn run_action(&self, action: &Action) {
+        log::debug!("Running action '{}'", action);
+        let command = match action {
+            Action::Switch(vdesk_id) => DesktopCommand::Switch(*vdesk_id),
+            Action::MoveWindow(vdesk_id) => DesktopCommand::MoveActiveWindow(*vdesk_id),
+            // Resolved by the command worker against the latest desktop state
+            Action::Goto(name) => DesktopCommand::SwitchByName(name.clone()),
+            // Navigation is left to the plugin so it matches its keybinds
+            Action::Next => DesktopCommand::NextDesk,
+            Action::Prev => DesktopCommand::PrevDesk,
+            Action::NextPopulated => DesktopCommand::CycleDesks,
+            Action::PrevPopulated => DesktopCommand::BackCycleDesks,
+            Action::Last => DesktopCommand::LastDesk,
+            Action::Reset => DesktopCommand::Reset,
+            Action::Resync => return self.resync(),
+            Action::DumpMetrics => return self.force_metrics_log(),
+        };
+        self.commands.submit(command);
     }
 }
 
@@ -338,7 +459,7 @@ mod tests {
     #[test]
     fn test_widget_update_algorithm_complexity() {
         use std::collections::BTreeMap;
-        let visible_vdesks = vec![
+        let visible_vdesks = [
             crate::vdesk::VirtualDesktop {
                 id: 1,
                 name: "Desktop 1".to_string(),
@@ -346,6 +467,7 @@ mod tests {
                 populated: true,
                 window_count: 0,
                 workspaces: vec![],
+                urgent: false,
             },
             crate::vdesk::VirtualDesktop {
                 id: 3,
@@ -354,6 +476,7 @@ mod tests {
                 populated: false,
                 window_count: 0,
                 workspaces: vec![],
+                urgent: false,
             },
         ];
 
@@ -411,10 +534,10 @@ mod tests {
         let wrapper: ConfigWrapper = serde_json::from_str(nested_json).unwrap();
         let config: ModuleConfig = wrapper.into();
         assert_eq!(config.format, "{icon} {name}");
-        assert_eq!(config.show_empty, true);
+        assert!(config.show_empty);
         assert_eq!(config.separator, " | ");
         assert_eq!(config.format_icons.get("1"), Some(&"🏠".to_string()));
-        assert_eq!(config.show_window_count, true);
+        assert!(config.show_window_count);
         assert_eq!(config.sort_by, crate::config::SortStrategy::FocusedFirst);
         assert_eq!(config.retry_max, 15);
         assert_eq!(config.retry_base_delay_ms, 750);
@@ -432,14 +555,38 @@ mod tests {
         let wrapper: ConfigWrapper = serde_json::from_str(direct_json).unwrap();
         let config: ModuleConfig = wrapper.into();
         assert_eq!(config.format, "{icon} {name}");
-        assert_eq!(config.show_empty, true);
+        assert!(config.show_empty);
         assert_eq!(config.separator, " | ");
         assert_eq!(config.format_icons.get("1"), Some(&"🏠".to_string()));
-        assert_eq!(config.show_window_count, true);
+        assert!(config.show_window_count);
         assert_eq!(config.sort_by, crate::config::SortStrategy::FocusedFirst);
         assert_eq!(config.retry_max, 15);
         assert_eq!(config.retry_base_delay_ms, 750);
     }
+
+    #[test]
+    fn test_waybar_config_collects_unknown_keys() {
+        let json = r#"{
+            "module_path": "/usr/lib/waybar/libwaybar_vd.so",
+            "show-empty": true,
+            "sort-by": "name",
+            "formt": "{name}"
+        }"#;
+
+        let wrapper: ConfigWrapper = serde_json::from_str(json).unwrap();
+        let (config, errors) = wrapper.into_config();
+        assert!(errors.is_empty());
+        assert!(config.show_empty);
+        assert_eq!(config.sort_by, crate::config::SortStrategy::Name);
+
+        let mut keys: Vec<&str> = config.unknown_keys.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["formt", "module_path"]);
+
+        let unknown = crate::config::find_unknown_keys(keys);
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].suggestion.as_deref(), Some("format"));
+    }
 }
 
 // Export the module
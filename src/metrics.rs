@@ -1,10 +1,12 @@
 //! Simple performance metrics for the virtual desktops module
 
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
 use std::time::Instant;
 
 use serde::Serialize;
 
+use crate::health::HealthState;
+
 /// Simple performance metrics focused on the O(n²) optimization
 #[derive(Debug)]
 pub struct PerformanceMetrics {
@@ -12,10 +14,22 @@ pub struct PerformanceMetrics {
     widget_reorder_count: AtomicU64,
     widget_reorder_optimized_count: AtomicU64,
     widget_update_duration_micros: AtomicU64,
+    widget_removed_count: AtomicU64,
 
     // Basic error tracking
     ipc_error_count: AtomicU64,
 
+    // Event coalescing
+    events_received_count: AtomicU64,
+    state_updates_emitted_count: AtomicU64,
+
+    // Commands from clicks, scrolling and actions
+    commands_executed_count: AtomicU64,
+
+    // Monitor supervisor health
+    health_state: AtomicU8,
+    health_transition_count: AtomicU64,
+
     // Startup tracking
     uptime_start: Instant,
 }
@@ -34,7 +48,19 @@ pub struct MetricsSnapshot {
     pub widget_reorders_optimized: u64,
     pub reorder_optimization_rate: f64,
     pub avg_widget_update_micros: f64,
+    pub widgets_removed_total: u64,
     pub ipc_errors_total: u64,
+    pub events_received_total: u64,
+    pub state_updates_emitted_total: u64,
+    pub commands_executed_total: u64,
+    pub health_state: HealthState,
+    pub health_transitions_total: u64,
+}
+
+impl Default for PerformanceMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PerformanceMetrics {
@@ -44,7 +70,13 @@ impl PerformanceMetrics {
             widget_reorder_count: AtomicU64::new(0),
             widget_reorder_optimized_count: AtomicU64::new(0),
             widget_update_duration_micros: AtomicU64::new(0),
+            widget_removed_count: AtomicU64::new(0),
             ipc_error_count: AtomicU64::new(0),
+            events_received_count: AtomicU64::new(0),
+            state_updates_emitted_count: AtomicU64::new(0),
+            commands_executed_count: AtomicU64::new(0),
+            health_state: AtomicU8::new(HealthState::Connecting as u8),
+            health_transition_count: AtomicU64::new(0),
             uptime_start: Instant::now(),
         }
     }
@@ -65,11 +97,37 @@ impl PerformanceMetrics {
         }
     }
 
+    /// Record removal of a widget whose virtual desktop no longer exists
+    pub fn record_widget_removed(&self) {
+        self.widget_removed_count.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Record an IPC error
     pub fn record_ipc_error(&self) {
         self.ipc_error_count.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record a state-changing Hyprland event
+    pub fn record_event_received(&self) {
+        self.events_received_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a state update sent to the UI after coalescing
+    pub fn record_state_update_emitted(&self) {
+        self.state_updates_emitted_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a command run through the command queue
+    pub fn record_command_executed(&self) {
+        self.commands_executed_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a change in the monitor's connection health
+    pub fn record_health_transition(&self, state: HealthState) {
+        self.health_state.store(state as u8, Ordering::Relaxed);
+        self.health_transition_count.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Get current metrics snapshot
     pub fn snapshot(&self) -> MetricsSnapshot {
         let uptime = self.uptime_start.elapsed();
@@ -87,7 +145,13 @@ impl PerformanceMetrics {
             avg_widget_update_micros: if reorders_total > 0 {
                 total_update_micros as f64 / reorders_total as f64
             } else { 0.0 },
+            widgets_removed_total: self.widget_removed_count.load(Ordering::Relaxed),
             ipc_errors_total: self.ipc_error_count.load(Ordering::Relaxed),
+            events_received_total: self.events_received_count.load(Ordering::Relaxed),
+            state_updates_emitted_total: self.state_updates_emitted_count.load(Ordering::Relaxed),
+            commands_executed_total: self.commands_executed_count.load(Ordering::Relaxed),
+            health_state: HealthState::from_u8(self.health_state.load(Ordering::Relaxed)),
+            health_transitions_total: self.health_transition_count.load(Ordering::Relaxed),
         }
     }
 
@@ -100,6 +164,11 @@ impl PerformanceMetrics {
                   snapshot.widget_reorders_total, snapshot.widget_reorders_optimized,
                   snapshot.reorder_optimization_rate * 100.0);
         log::info!("  Avg update time: {:.2}ms", snapshot.avg_widget_update_micros / 1000.0);
+        log::info!("  Widgets removed: {}", snapshot.widgets_removed_total);
+        log::info!("  Events: {} received, {} updates emitted",
+                  snapshot.events_received_total, snapshot.state_updates_emitted_total);
+        log::info!("  Commands executed: {}", snapshot.commands_executed_total);
+        log::info!("  Health: {} ({} transitions)", snapshot.health_state, snapshot.health_transitions_total);
         if snapshot.ipc_errors_total > 0 {
             log::warn!("  IPC errors: {}", snapshot.ipc_errors_total);
         }
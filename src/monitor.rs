@@ -1,26 +1,139 @@
 //! Background monitoring for virtual desktop state changes
 
 use crate::config::ModuleConfig;
-use crate::errors::Result;
-use crate::hyprland::HyprlandIPC;
+use crate::errors::{ErrorSeverity, Result, VirtualDesktopError};
+use crate::health::{HealthReport, HealthState};
+use crate::hyprland::{is_connection_error, HyprlandEvent, HyprlandIPC};
+use crate::metrics::PerformanceMetrics;
 use crate::vdesk::VirtualDesktopsManager;
 
+use futures::StreamExt;
 use std::sync::Arc;
-use tokio::sync::{Mutex, mpsc};
+use tokio::sync::{Mutex, Notify, watch};
 
 type VdeskUpdateMessage = Vec<crate::vdesk::VirtualDesktop>;
 
-/// Resilient monitoring loop
+/// Longest wait between reconnect attempts
+const MAX_RECONNECT_DELAY_MS: u64 = 30000;
+
+/// Delay before retrying after a minor error
+const QUICK_RETRY_DELAY_MS: u64 = 100;
+
+/// Consecutive failures after which the connection is reported as disconnected
+const DISCONNECTED_AFTER_FAILURES: u32 = 3;
+
+/// Running monitor task and the way to stop it
+struct MonitorTask {
+    handle: tokio::task::JoinHandle<()>,
+    shutdown: tokio::sync::oneshot::Sender<()>,
+}
+
+/// Owns the monitor task so it can be restarted after [`resilient_monitor_loop`] gives up
+pub struct MonitorSupervisor {
+    runtime: tokio::runtime::Handle,
+    manager: Arc<Mutex<VirtualDesktopsManager>>,
+    config: watch::Receiver<ModuleConfig>,
+    metrics: Arc<PerformanceMetrics>,
+    tx: watch::Sender<VdeskUpdateMessage>,
+    health: watch::Sender<HealthReport>,
+    resync: Arc<Notify>,
+    task: std::sync::Mutex<Option<MonitorTask>>,
+}
+
+impl MonitorSupervisor {
+    pub fn new(
+        runtime: tokio::runtime::Handle,
+        manager: Arc<Mutex<VirtualDesktopsManager>>,
+        config: watch::Receiver<ModuleConfig>,
+        metrics: Arc<PerformanceMetrics>,
+        tx: watch::Sender<VdeskUpdateMessage>,
+        health: watch::Sender<HealthReport>,
+    ) -> Self {
+        Self {
+            runtime,
+            manager,
+            config,
+            metrics,
+            tx,
+            health,
+            resync: Arc::new(Notify::new()),
+            task: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Whether the monitor task is still running
+    pub fn is_running(&self) -> bool {
+        self.task
+            .lock()
+            .map(|task| task.as_ref().is_some_and(|task| !task.handle.is_finished()))
+            .unwrap_or(false)
+    }
+
+    /// Start the monitor task unless it is already running
+    pub fn start(&self) {
+        let Ok(mut task) = self.task.lock() else {
+            return;
+        };
+        if task.as_ref().is_some_and(|task| !task.handle.is_finished()) {
+            return;
+        }
+
+        let (shutdown, shutdown_rx) = tokio::sync::oneshot::channel();
+        let loop_future = resilient_monitor_loop(
+            Arc::clone(&self.manager),
+            self.config.clone(),
+            Arc::clone(&self.metrics),
+            shutdown_rx,
+            self.tx.clone(),
+            self.health.clone(),
+            Arc::clone(&self.resync),
+        );
+        let handle = self.runtime.spawn(async move {
+            if let Err(e) = loop_future.await {
+                log::error!("Resilient monitor loop failed: {e}");
+            }
+        });
+        *task = Some(MonitorTask { handle, shutdown });
+    }
+
+    /// Reconnect now: a running monitor skips its backoff and resyncs, a stopped one is restarted
+    pub fn reconnect(&self) {
+        if self.is_running() {
+            self.resync.notify_one();
+        } else {
+            log::info!("Monitor has stopped, restarting it");
+            set_health(&self.health, &self.metrics, HealthState::Connecting, None);
+            self.start();
+        }
+    }
+
+    /// Ask the monitor task to shut down, returning it so the caller can wait for it
+    pub fn stop(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let task = self.task.lock().ok()?.take()?;
+        let _ = task.shutdown.send(());
+        Some(task.handle)
+    }
+}
+
+/// Supervised monitoring loop
+///
+/// Each cycle reconnects from scratch, re-running instance discovery, so a restarted
+/// Hyprland is picked up even when its signature changed. The severity of a failed
+/// cycle decides whether to retry quickly, back off, or stop. Retry settings are read
+/// from the latest configuration on every failure. Stopping is reported as
+/// [`HealthState::Stopped`]; only [`MonitorSupervisor::reconnect`] starts it again.
 pub async fn resilient_monitor_loop(
     manager: Arc<Mutex<VirtualDesktopsManager>>,
-    config: ModuleConfig,
+    config: watch::Receiver<ModuleConfig>,
+    metrics: Arc<PerformanceMetrics>,
     mut shutdown_rx: tokio::sync::oneshot::Receiver<()>,
-    tx: mpsc::Sender<VdeskUpdateMessage>,
+    tx: watch::Sender<VdeskUpdateMessage>,
+    health: watch::Sender<HealthReport>,
+    resync: Arc<Notify>,
 ) -> Result<()> {
     log::info!("Starting resilient virtual desktop monitoring...");
 
     let mut consecutive_failures = 0;
-    const MAX_CONSECUTIVE_FAILURES: u32 = 5;
 
     loop {
         tokio::select! {
@@ -28,7 +141,7 @@ pub async fn resilient_monitor_loop(
                 log::info!("Graceful shutdown requested for monitor loop");
                 break;
             }
-            result = monitor_virtual_desktops_once(&manager, &config, tx.clone()) => {
+            result = monitor_virtual_desktops_once(&manager, config.clone(), &metrics, &tx, &health, &resync) => {
                 match result {
                     Ok(_) => {
                         consecutive_failures = 0;
@@ -36,20 +149,34 @@ pub async fn resilient_monitor_loop(
                     }
                     Err(e) => {
                         consecutive_failures += 1;
-                        log::error!("Monitor failure {}/{}: {}",
-                                  consecutive_failures, MAX_CONSECUTIVE_FAILURES, e);
+                        let severity = e.severity();
+                        log::error!("Monitor failure {} ({:?}): {}", consecutive_failures, severity, e);
+
+                        let (base_delay_ms, max_attempts) = {
+                            let settings = config.borrow();
+                            (settings.retry_base_delay_ms, settings.max_reconnect_attempts)
+                        };
 
-                        if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
-                            return Err(crate::errors::VirtualDesktopError::RetryExhausted {
-                                attempts: MAX_CONSECUTIVE_FAILURES,
+                        let Some(delay_ms) = retry_delay_ms(severity, base_delay_ms, consecutive_failures) else {
+                            log::error!("Stopping monitor after {:?} error", severity);
+                            set_health(&health, &metrics, HealthState::Stopped, Some(e.to_string()));
+                            return Err(e);
+                        };
+
+                        if max_attempts > 0 && consecutive_failures >= max_attempts {
+                            let e = VirtualDesktopError::RetryExhausted {
+                                attempts: max_attempts,
                                 last_error: e.to_string(),
-                            });
+                            };
+                            set_health(&health, &metrics, HealthState::Stopped, Some(e.to_string()));
+                            return Err(e);
                         }
 
-                        // Exponential backoff for recovery
-                        let delay = tokio::time::Duration::from_millis(
-                            config.retry_base_delay_ms * 2_u64.pow(consecutive_failures - 1)
-                        );
+                        let current = health.borrow().state;
+                        let next = health_after_failure(current, consecutive_failures);
+                        set_health(&health, &metrics, next, Some(e.to_string()));
+
+                        let delay = tokio::time::Duration::from_millis(delay_ms);
                         log::info!("Waiting {:?} before retry attempt", delay);
 
                         tokio::select! {
@@ -60,6 +187,9 @@ pub async fn resilient_monitor_loop(
                             _ = tokio::time::sleep(delay) => {
                                 log::debug!("Recovery delay completed, retrying...");
                             }
+                            _ = resync.notified() => {
+                                log::info!("Reconnect requested, retrying now");
+                            }
                         }
                     }
                 }
@@ -71,50 +201,351 @@ pub async fn resilient_monitor_loop(
     Ok(())
 }
 
+/// How long to wait before the next cycle, or `None` when the error cannot be retried
+fn retry_delay_ms(severity: ErrorSeverity, base_delay_ms: u64, consecutive_failures: u32) -> Option<u64> {
+    match severity {
+        ErrorSeverity::Fatal | ErrorSeverity::Critical => None,
+        ErrorSeverity::Recoverable => Some(reconnect_delay_ms(base_delay_ms, consecutive_failures)),
+        ErrorSeverity::Minor => Some(QUICK_RETRY_DELAY_MS),
+    }
+}
+
+/// Exponential backoff between reconnect attempts, capped so retrying forever stays responsive
+fn reconnect_delay_ms(base_delay_ms: u64, consecutive_failures: u32) -> u64 {
+    let exponent = consecutive_failures.saturating_sub(1).min(16);
+    base_delay_ms
+        .saturating_mul(1 << exponent)
+        .min(MAX_RECONNECT_DELAY_MS)
+}
+
+/// Health to report after a failed cycle
+fn health_after_failure(current: HealthState, consecutive_failures: u32) -> HealthState {
+    if consecutive_failures >= DISCONNECTED_AFTER_FAILURES {
+        return HealthState::Disconnected;
+    }
+    match current {
+        HealthState::Live | HealthState::Degraded => HealthState::Degraded,
+        state => state,
+    }
+}
+
+/// Publish the health state, only waking the UI when the report changes and recording
+/// a transition when the state itself changes
+pub fn set_health(
+    health: &watch::Sender<HealthReport>,
+    metrics: &PerformanceMetrics,
+    state: HealthState,
+    error: Option<String>,
+) {
+    let report = HealthReport::new(state, error);
+    let mut previous = state;
+    health.send_if_modified(|current| {
+        previous = current.state;
+        std::mem::replace(current, report.clone()) != report
+    });
+    if previous != state {
+        log::info!("Hyprland connection is now {}", state);
+        metrics.record_health_transition(state);
+    }
+}
+
 /// Single monitoring cycle
+///
+/// A reloaded configuration is applied without reconnecting; settings that only affect
+/// connecting, such as the instance signature, take effect on the next cycle.
 async fn monitor_virtual_desktops_once(
     manager: &Arc<Mutex<VirtualDesktopsManager>>,
-    config: &ModuleConfig,
-    tx: mpsc::Sender<VdeskUpdateMessage>,
+    mut config: watch::Receiver<ModuleConfig>,
+    metrics: &PerformanceMetrics,
+    tx: &watch::Sender<VdeskUpdateMessage>,
+    health: &watch::Sender<HealthReport>,
+    resync: &Notify,
 ) -> Result<()> {
     log::debug!("Starting monitor cycle...");
 
+    let settings = config.borrow_and_update().clone();
+    manager.lock().await.set_config(&settings);
+
     // Create IPC connection
-    let mut ipc = HyprlandIPC::with_config(config.retry_max, config.retry_base_delay_ms).await
+    let ipc = HyprlandIPC::with_config(&settings).await
         .map_err(|e| crate::errors::VirtualDesktopError::IpcConnection {
             source: std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string())
         })?;
 
     log::debug!("Successfully connected to Hyprland IPC for monitoring");
 
-    // Listen for events
+    // Desktops may have changed while disconnected, e.g. after a compositor restart
+    refresh_state(manager, metrics, tx, health).await?;
+
+    let mut debounce = tokio::time::Duration::from_millis(settings.update_debounce_ms);
+
+    // Consume the persistent event stream
+    let mut events = ipc.event_stream();
     loop {
-        match ipc.listen_for_events().await {
-            Ok(event) => {
-                if event.starts_with("vdesk>>") {
-                    log::debug!("Received vdesk event: {}", event);
-                    let mut mgr = manager.lock().await;
-                    if let Err(e) = mgr.update_state().await {
-                        log::error!("Failed to update virtual desktop state: {}", e);
-                    } else {
-                        log::debug!("Virtual desktop state updated, sending to UI thread.");
-                        // Get the new state and send it through the channel
-                        let vdesks = mgr.get_virtual_desktops();
-                        if let Err(e) = tx.send(vdesks).await {
-                            log::error!("Failed to send update to UI thread: {}. Channel closed.", e);
-                            // Channel is closed, so we should exit the loop.
-                            return Err(crate::errors::VirtualDesktopError::Internal {
-                                message: "UI channel closed".to_string(),
-                            });
-                        }
+        let event = tokio::select! {
+            event = events.next() => event,
+            _ = resync.notified() => {
+                log::info!("Resync requested, refreshing state");
+                refresh_state(manager, metrics, tx, health).await?;
+                continue;
+            }
+            Ok(()) = config.changed() => {
+                let settings = config.borrow_and_update().clone();
+                debounce = tokio::time::Duration::from_millis(settings.update_debounce_ms);
+                manager.lock().await.set_config(&settings);
+                log::info!("Applied reloaded configuration to the monitor");
+                continue;
+            }
+        };
+        let Some(event) = event else { break };
+        let event = HyprlandEvent::parse(&event.map_err(event_stream_error)?);
+        if !event.affects_virtual_desktops() {
+            continue;
+        }
+        track_urgency(manager, &event).await;
+        log::debug!("Received state-changing event: {:?}", event);
+        metrics.record_event_received();
+
+        // Coalesce further events within the debounce window so a burst results in a single refresh.
+        // Events already queued past the deadline are still drained, and the state is always
+        // fetched after the last of them, so the trailing state is never lost. A resync
+        // request cuts the window short.
+        let deadline = tokio::time::Instant::now() + debounce;
+        let mut coalesced = 0;
+        let mut stream_error = None;
+        loop {
+            let next = tokio::select! {
+                next = tokio::time::timeout_at(deadline, events.next()) => next,
+                _ = resync.notified() => {
+                    log::info!("Resync requested, refreshing state without waiting for debounce");
+                    break;
+                }
+            };
+            match next {
+                Ok(Some(Ok(line))) => {
+                    let event = HyprlandEvent::parse(&line);
+                    if event.affects_virtual_desktops() {
+                        track_urgency(manager, &event).await;
+                        metrics.record_event_received();
+                        coalesced += 1;
                     }
                 }
+                Ok(Some(Err(e))) => {
+                    stream_error = Some(e);
+                    break;
+                }
+                Ok(None) | Err(_) => break,
             }
-            Err(e) => {
-                return Err(crate::errors::VirtualDesktopError::IpcConnection { 
-                    source: std::io::Error::new(std::io::ErrorKind::BrokenPipe, e.to_string()) 
-                });
+        }
+        if coalesced > 0 {
+            log::debug!("Coalesced {} additional events into one state refresh", coalesced);
+        }
+
+        refresh_state(manager, metrics, tx, health).await?;
+
+        if let Some(e) = stream_error {
+            return Err(event_stream_error(e));
+        }
+    }
+
+    Err(crate::errors::VirtualDesktopError::IpcConnection {
+        source: std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Event stream ended")
+    })
+}
+
+/// Remember windows requesting attention so the next refresh can flag their desktops
+async fn track_urgency(manager: &Mutex<VirtualDesktopsManager>, event: &HyprlandEvent) {
+    if let HyprlandEvent::Urgent { address } = event {
+        manager.lock().await.mark_urgent(address);
+    }
+}
+
+/// Fetch the current state from Hyprland and send it to the UI thread
+async fn refresh_state(
+    manager: &Arc<Mutex<VirtualDesktopsManager>>,
+    metrics: &PerformanceMetrics,
+    tx: &watch::Sender<VdeskUpdateMessage>,
+    health: &watch::Sender<HealthReport>,
+) -> Result<()> {
+    let mut mgr = manager.lock().await;
+    if let Err(e) = mgr.update_state().await {
+        // Connection failures end the cycle so the retry path reconnects; other failures wait for the next event
+        if is_connection_error(&e) {
+            return Err(match e.downcast::<VirtualDesktopError>() {
+                Ok(e) => e,
+                Err(e) => connection_error(std::io::ErrorKind::ConnectionRefused, e),
+            });
+        }
+        // Still connected, so keep listening for events but report the stale state
+        log::error!("Failed to update virtual desktop state: {}", e);
+        set_health(health, metrics, HealthState::Degraded, Some(e.to_string()));
+        return Ok(());
+    }
+
+    log::debug!("Virtual desktop state updated, sending to UI thread.");
+    // Publish the new state, replacing any snapshot the UI has not rendered yet
+    let vdesks = mgr.get_virtual_desktops();
+    if let Err(e) = tx.send(vdesks) {
+        log::error!("Failed to send update to UI thread: {}. Channel closed.", e);
+        // Channel is closed, so the monitor should stop.
+        return Err(crate::errors::VirtualDesktopError::Internal {
+            message: "UI channel closed".to_string(),
+        });
+    }
+    metrics.record_state_update_emitted();
+    set_health(health, metrics, HealthState::Live, None);
+
+    Ok(())
+}
+
+fn event_stream_error(e: anyhow::Error) -> VirtualDesktopError {
+    connection_error(std::io::ErrorKind::BrokenPipe, e)
+}
+
+fn connection_error(kind: std::io::ErrorKind, e: anyhow::Error) -> VirtualDesktopError {
+    VirtualDesktopError::IpcConnection {
+        source: std::io::Error::new(kind, e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_delay_is_capped() {
+        assert_eq!(reconnect_delay_ms(500, 1), 500);
+        assert_eq!(reconnect_delay_ms(500, 2), 1000);
+        assert_eq!(reconnect_delay_ms(500, 6), 16000);
+        assert_eq!(reconnect_delay_ms(500, 7), MAX_RECONNECT_DELAY_MS);
+        // Retrying forever must not overflow
+        assert_eq!(reconnect_delay_ms(500, u32::MAX), MAX_RECONNECT_DELAY_MS);
+    }
+
+    #[test]
+    fn test_severity_decides_retry() {
+        assert_eq!(retry_delay_ms(ErrorSeverity::Minor, 500, 4), Some(QUICK_RETRY_DELAY_MS));
+        assert_eq!(retry_delay_ms(ErrorSeverity::Recoverable, 500, 2), Some(1000));
+        assert_eq!(retry_delay_ms(ErrorSeverity::Critical, 500, 1), None);
+        assert_eq!(retry_delay_ms(ErrorSeverity::Fatal, 500, 1), None);
+
+        // The UI going away is critical and stops the monitor
+        let ui_gone = VirtualDesktopError::Internal { message: "UI channel closed".to_string() };
+        assert_eq!(retry_delay_ms(ui_gone.severity(), 500, 1), None);
+    }
+
+    #[tokio::test]
+    async fn test_loop_reports_stopped_once_attempts_run_out() {
+        let settings = ModuleConfig {
+            instance_signature: Some("waybar-vd-test-missing".to_string()),
+            max_reconnect_attempts: 1,
+            ..Default::default()
+        };
+        let manager = Arc::new(Mutex::new(VirtualDesktopsManager::with_config(&settings)));
+        let (_config_tx, config_rx) = watch::channel(settings);
+        let (_shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+        let (tx, _rx) = watch::channel(Vec::new());
+        let (health, health_rx) = watch::channel(HealthReport::default());
+        let metrics = Arc::new(PerformanceMetrics::new());
+
+        let result = resilient_monitor_loop(
+            manager,
+            config_rx,
+            Arc::clone(&metrics),
+            shutdown_rx,
+            tx,
+            health,
+            Arc::new(Notify::new()),
+        )
+        .await;
+
+        assert!(matches!(result, Err(VirtualDesktopError::RetryExhausted { attempts: 1, .. })));
+        assert_eq!(health_rx.borrow().state, HealthState::Stopped);
+        assert_eq!(metrics.snapshot().health_state, HealthState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_restarts_stopped_monitor() {
+        let settings = ModuleConfig {
+            instance_signature: Some("waybar-vd-test-missing".to_string()),
+            max_reconnect_attempts: 1,
+            ..Default::default()
+        };
+        let manager = Arc::new(Mutex::new(VirtualDesktopsManager::with_config(&settings)));
+        let (_config_tx, config_rx) = watch::channel(settings);
+        let (tx, _rx) = watch::channel(Vec::new());
+        let (health, mut health_rx) = watch::channel(HealthReport::default());
+        let metrics = Arc::new(PerformanceMetrics::new());
+        let supervisor = MonitorSupervisor::new(
+            tokio::runtime::Handle::current(),
+            manager,
+            config_rx,
+            Arc::clone(&metrics),
+            tx,
+            health,
+        );
+
+        let wait_until_stopped = |health_rx: &watch::Receiver<HealthReport>| {
+            let mut health_rx = health_rx.clone();
+            async move {
+                tokio::time::timeout(
+                    tokio::time::Duration::from_secs(5),
+                    health_rx.wait_for(|report| report.state == HealthState::Stopped),
+                )
+                .await
+                .expect("monitor should stop")
+                .unwrap();
             }
+        };
+
+        supervisor.start();
+        wait_until_stopped(&health_rx).await;
+        // Health is published just before the task returns
+        while supervisor.is_running() {
+            tokio::task::yield_now().await;
         }
+
+        // A click on the placeholder starts a fresh monitor, which gives up again here
+        supervisor.reconnect();
+        assert_ne!(health_rx.borrow_and_update().state, HealthState::Stopped);
+        wait_until_stopped(&health_rx).await;
+        assert_eq!(metrics.snapshot().health_transitions_total, 3);
+
+        assert!(supervisor.stop().is_some());
+        assert!(supervisor.stop().is_none());
+    }
+
+    #[test]
+    fn test_health_after_failure() {
+        assert_eq!(health_after_failure(HealthState::Live, 1), HealthState::Degraded);
+        assert_eq!(health_after_failure(HealthState::Degraded, 2), HealthState::Degraded);
+        // Never connected yet, so still connecting
+        assert_eq!(health_after_failure(HealthState::Connecting, 1), HealthState::Connecting);
+        assert_eq!(health_after_failure(HealthState::Connecting, DISCONNECTED_AFTER_FAILURES), HealthState::Disconnected);
+        assert_eq!(health_after_failure(HealthState::Live, DISCONNECTED_AFTER_FAILURES), HealthState::Disconnected);
+    }
+
+    #[test]
+    fn test_set_health_only_notifies_on_change() {
+        let metrics = PerformanceMetrics::new();
+        let (tx, mut rx) = watch::channel(HealthReport::default());
+        rx.borrow_and_update();
+
+        set_health(&tx, &metrics, HealthState::Connecting, None);
+        assert!(!rx.has_changed().unwrap());
+
+        // A new error message wakes the UI without counting as a transition
+        set_health(&tx, &metrics, HealthState::Connecting, Some("socket not found".to_string()));
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(rx.borrow_and_update().error.as_deref(), Some("socket not found"));
+        assert_eq!(metrics.snapshot().health_transitions_total, 0);
+
+        set_health(&tx, &metrics, HealthState::Live, None);
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(*rx.borrow_and_update(), HealthReport::new(HealthState::Live, None));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.health_state, HealthState::Live);
+        assert_eq!(snapshot.health_transitions_total, 1);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,184 @@
+//! Live reloading of the module configuration
+
+use crate::config::ModuleConfig;
+use crate::errors::VirtualDesktopError;
+
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+
+/// How often the override file is checked for changes
+const OVERRIDE_POLL_INTERVAL_MS: u64 = 1000;
+
+/// Where the module configuration comes from: Waybar's block plus an optional override file
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    base: Map<String, Value>,
+}
+
+impl ConfigSource {
+    /// Keep Waybar's block so it can be parsed again on every reload
+    pub fn new(base: Map<String, Value>) -> Self {
+        Self { base }
+    }
+
+    /// Override file named by Waybar's block, with `~` expanded
+    pub fn override_path(&self) -> Option<PathBuf> {
+        let (config, _) = ModuleConfig::from_json_map(self.base.clone());
+        config.config_file.as_deref().map(expand_home)
+    }
+
+    /// Parse the configuration, applying the override file when one is configured
+    ///
+    /// A missing or malformed override file is reported and Waybar's block is used alone.
+    pub fn load(&self) -> (ModuleConfig, Vec<VirtualDesktopError>) {
+        let Some(path) = self.override_path() else {
+            return ModuleConfig::from_json_map(self.base.clone());
+        };
+
+        match read_override_file(&path) {
+            Ok(overrides) => {
+                let mut options = self.base.clone();
+                merge_options(&mut options, overrides);
+                ModuleConfig::from_json_map(options)
+            }
+            Err(e) => {
+                let (config, mut errors) = ModuleConfig::from_json_map(self.base.clone());
+                errors.insert(0, e);
+                (config, errors)
+            }
+        }
+    }
+}
+
+/// Apply override options on top of the base ones
+///
+/// Keys match regardless of spelling, so `show-empty` in the file replaces `show_empty`.
+pub fn merge_options(base: &mut Map<String, Value>, overrides: Map<String, Value>) {
+    for (key, value) in overrides {
+        let normalized = normalize_key(&key);
+        base.retain(|existing, _| normalize_key(existing) != normalized);
+        base.insert(key, value);
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    key.replace('-', "_")
+}
+
+/// Read an override file, which must contain a JSON object of options
+pub fn read_override_file(path: &Path) -> Result<Map<String, Value>, VirtualDesktopError> {
+    let shown = path.display().to_string();
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| VirtualDesktopError::invalid_config("config_file", &shown, &e.to_string()))?;
+    match serde_json::from_str(&contents) {
+        Ok(Value::Object(options)) => Ok(options),
+        Ok(_) => Err(VirtualDesktopError::invalid_config("config_file", &shown, "must contain a JSON object")),
+        Err(e) => Err(VirtualDesktopError::invalid_config("config_file", &shown, &e.to_string())),
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Modification time and size, or `None` while the file does not exist
+fn fingerprint(path: &Path) -> Option<(Option<SystemTime>, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
+/// Poll the override file and request a reload whenever it changes, appears or disappears
+///
+/// Stops once the receiving end of `reload` is gone.
+pub async fn watch_override_file(path: PathBuf, reload: mpsc::UnboundedSender<()>) {
+    log::info!("Watching {} for configuration changes", path.display());
+    let mut last = fingerprint(&path);
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(OVERRIDE_POLL_INTERVAL_MS));
+    loop {
+        interval.tick().await;
+        let current = fingerprint(&path);
+        if current == last {
+            continue;
+        }
+        last = current;
+        log::info!("{} changed, reloading configuration", path.display());
+        if reload.send(()).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn options(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => panic!("expected an object"),
+        }
+    }
+
+    #[test]
+    fn test_merge_options_replaces_either_spelling() {
+        let mut base = options(json!({"show-empty": false, "format": "{name}", "sort_by": "number"}));
+        merge_options(&mut base, options(json!({"show_empty": true, "sort-by": "name"})));
+
+        assert_eq!(base.len(), 3);
+        assert_eq!(base["show_empty"], json!(true));
+        assert_eq!(base["sort-by"], json!("name"));
+        assert_eq!(base["format"], json!("{name}"));
+    }
+
+    #[test]
+    fn test_load_applies_override_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("override.json");
+        std::fs::write(&path, r#"{"format": "<{name}>", "retry_max": 0}"#).unwrap();
+
+        let source = ConfigSource::new(options(json!({
+            "config-file": path.to_str().unwrap(),
+            "format": "{name}",
+            "show_empty": true
+        })));
+        let (config, errors) = source.load();
+
+        assert_eq!(config.format, "<{name}>");
+        assert!(config.show_empty);
+        // Overrides are validated like Waybar's block
+        assert_eq!(config.retry_max, 10);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_load_reports_unusable_override_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.json");
+        std::fs::write(&path, "[1, 2]").unwrap();
+
+        let source = ConfigSource::new(options(json!({"config_file": path.to_str().unwrap(), "show_empty": true})));
+        let (config, errors) = source.load();
+        assert!(config.show_empty);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("config_file"));
+
+        std::fs::remove_file(&path).unwrap();
+        let (_, errors) = source.load();
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_expand_home() {
+        assert_eq!(expand_home("/etc/waybar-vd.json"), PathBuf::from("/etc/waybar-vd.json"));
+        if let Some(home) = std::env::var_os("HOME") {
+            assert_eq!(expand_home("~/vd.json"), PathBuf::from(home).join("vd.json"));
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Waybar refresh signals and what they ask the module to do
+
+use crate::config::ModuleConfig;
+
+/// Behaviour triggered by a refresh signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalAction {
+    /// Fetch the full state from Hyprland right away
+    Resync,
+    /// Re-read the configuration and apply it in place
+    ReloadConfig,
+    /// Log the performance metrics
+    DumpMetrics,
+}
+
+/// Largest N for which Waybar forwards `SIGRTMIN+N` to modules
+pub fn max_signal_offset() -> u32 {
+    (libc::SIGRTMAX() - libc::SIGRTMIN()).max(0) as u32
+}
+
+/// Signal number Waybar passes to `refresh` for `pkill -RTMIN+N waybar`
+///
+/// Waybar only handles `SIGRTMIN+1` up to `SIGRTMAX`, so other offsets have none.
+pub fn realtime_signal(offset: u32) -> Option<i32> {
+    if offset == 0 || offset > max_signal_offset() {
+        return None;
+    }
+    Some(libc::SIGRTMIN() + offset as i32)
+}
+
+/// Decide what a refresh signal means for this module
+///
+/// Waybar forwards every refresh signal to every module, so only the signals
+/// configured with `signal`, `reload_signal` or `metrics_signal` do anything.
+pub fn signal_action(config: &ModuleConfig, signal: i32) -> Option<SignalAction> {
+    let matches = |offset: Option<u32>| offset.and_then(realtime_signal) == Some(signal);
+
+    if matches(config.reload_signal) {
+        Some(SignalAction::ReloadConfig)
+    } else if matches(config.metrics_signal) {
+        Some(SignalAction::DumpMetrics)
+    } else if matches(config.signal) {
+        Some(SignalAction::Resync)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rtmin(offset: i32) -> i32 {
+        libc::SIGRTMIN() + offset
+    }
+
+    #[test]
+    fn test_realtime_signal() {
+        assert_eq!(realtime_signal(0), None);
+        assert_eq!(realtime_signal(1), Some(rtmin(1)));
+        assert_eq!(realtime_signal(max_signal_offset()), Some(libc::SIGRTMAX()));
+        assert_eq!(realtime_signal(max_signal_offset() + 1), None);
+    }
+
+    #[test]
+    fn test_signal_action_mapping() {
+        let config = ModuleConfig {
+            signal: Some(8),
+            reload_signal: Some(9),
+            metrics_signal: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(signal_action(&config, rtmin(8)), Some(SignalAction::Resync));
+        assert_eq!(signal_action(&config, rtmin(9)), Some(SignalAction::ReloadConfig));
+        assert_eq!(signal_action(&config, rtmin(10)), Some(SignalAction::DumpMetrics));
+        // Another module's signal
+        assert_eq!(signal_action(&config, rtmin(3)), None);
+    }
+
+    #[test]
+    fn test_unmapped_signals_ignored_without_signal() {
+        let config = ModuleConfig {
+            reload_signal: Some(9),
+            ..Default::default()
+        };
+        assert_eq!(signal_action(&config, rtmin(3)), None);
+        assert_eq!(signal_action(&config, rtmin(9)), Some(SignalAction::ReloadConfig));
+    }
+}
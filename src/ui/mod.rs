@@ -1,5 +1,8 @@
 //! GTK widget management for virtual desktop display
 
+pub mod scroll;
+pub mod shell;
 pub mod widgets;
 
-pub use widgets::{VirtualDesktopWidget, WidgetManager};
\ No newline at end of file
+pub use scroll::{ScrollAccumulator, ScrollDirection};
+pub use widgets::{VirtualDesktopWidget, WidgetManager};
@@ -0,0 +1,150 @@
+//! Scroll-wheel navigation between virtual desktops
+
+/// Direction of a single scroll step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+}
+
+/// Accumulates smooth-scroll deltas so a touchpad flick only moves one step per threshold
+#[derive(Debug)]
+pub struct ScrollAccumulator {
+    threshold: f64,
+    delta: f64,
+}
+
+impl ScrollAccumulator {
+    /// Create accumulator
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            delta: 0.0,
+        }
+    }
+
+    /// Add a smooth-scroll delta and return a step once the threshold is crossed
+    pub fn accumulate(&mut self, delta_y: f64) -> Option<ScrollDirection> {
+        self.delta += delta_y;
+
+        // A zero threshold still ignores zero-length deltas (e.g. touchpad scroll stop)
+        if self.delta.abs() < self.threshold.max(f64::EPSILON) {
+            return None;
+        }
+
+        let direction = if self.delta < 0.0 {
+            ScrollDirection::Up
+        } else {
+            ScrollDirection::Down
+        };
+        self.delta = 0.0;
+        Some(direction)
+    }
+
+    /// Change the distance needed for a step, e.g. after the configuration is reloaded
+    pub fn set_threshold(&mut self, threshold: f64) {
+        self.threshold = threshold;
+    }
+
+    /// Discard any partial delta
+    pub fn reset(&mut self) {
+        self.delta = 0.0;
+    }
+}
+
+/// Find the desktop to switch to when scrolling from the focused one
+///
+/// Scrolling up moves to the previous desktop in `order`, scrolling down to the next.
+/// Desktops rejected by `is_candidate` are skipped.
+pub fn neighbour_vdesk(
+    order: &[u32],
+    focused: Option<u32>,
+    direction: ScrollDirection,
+    wrap: bool,
+    is_candidate: impl Fn(u32) -> bool,
+) -> Option<u32> {
+    let len = order.len();
+    let current = focused.and_then(|id| order.iter().position(|&o| o == id))?;
+
+    for step in 1..len {
+        let index = match direction {
+            ScrollDirection::Up if wrap => (current + len - step) % len,
+            ScrollDirection::Down if wrap => (current + step) % len,
+            ScrollDirection::Up => current.checked_sub(step)?,
+            ScrollDirection::Down => {
+                let index = current + step;
+                if index >= len {
+                    return None;
+                }
+                index
+            }
+        };
+
+        if is_candidate(order[index]) {
+            return Some(order[index]);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighbour_without_wrap() {
+        let order = [1, 2, 3];
+        let any = |_| true;
+
+        assert_eq!(neighbour_vdesk(&order, Some(2), ScrollDirection::Up, false, any), Some(1));
+        assert_eq!(neighbour_vdesk(&order, Some(2), ScrollDirection::Down, false, any), Some(3));
+        assert_eq!(neighbour_vdesk(&order, Some(1), ScrollDirection::Up, false, any), None);
+        assert_eq!(neighbour_vdesk(&order, Some(3), ScrollDirection::Down, false, any), None);
+    }
+
+    #[test]
+    fn test_neighbour_with_wrap() {
+        let order = [1, 2, 3];
+        let any = |_| true;
+
+        assert_eq!(neighbour_vdesk(&order, Some(1), ScrollDirection::Up, true, any), Some(3));
+        assert_eq!(neighbour_vdesk(&order, Some(3), ScrollDirection::Down, true, any), Some(1));
+
+        // A single desktop has no neighbour
+        assert_eq!(neighbour_vdesk(&[1], Some(1), ScrollDirection::Down, true, any), None);
+        // Unknown focus has no neighbour
+        assert_eq!(neighbour_vdesk(&order, None, ScrollDirection::Down, true, any), None);
+    }
+
+    #[test]
+    fn test_neighbour_skips_rejected() {
+        let order = [1, 2, 3, 4];
+        let populated = |id| id != 2 && id != 3;
+
+        assert_eq!(neighbour_vdesk(&order, Some(1), ScrollDirection::Down, false, populated), Some(4));
+        assert_eq!(neighbour_vdesk(&order, Some(4), ScrollDirection::Up, false, populated), Some(1));
+        assert_eq!(neighbour_vdesk(&order, Some(4), ScrollDirection::Down, true, populated), Some(1));
+        assert_eq!(neighbour_vdesk(&order, Some(1), ScrollDirection::Down, false, |_| false), None);
+    }
+
+    #[test]
+    fn test_scroll_accumulator_threshold() {
+        let mut accumulator = ScrollAccumulator::new(1.0);
+
+        // Touchpad deltas accumulate until the threshold is crossed
+        assert_eq!(accumulator.accumulate(0.4), None);
+        assert_eq!(accumulator.accumulate(0.4), None);
+        assert_eq!(accumulator.accumulate(0.4), Some(ScrollDirection::Down));
+        // The accumulated delta resets after a step
+        assert_eq!(accumulator.accumulate(0.4), None);
+
+        accumulator.reset();
+        assert_eq!(accumulator.accumulate(-1.0), Some(ScrollDirection::Up));
+
+        // Zero threshold never fires on zero deltas
+        let mut accumulator = ScrollAccumulator::new(0.0);
+        assert_eq!(accumulator.accumulate(0.0), None);
+        assert_eq!(accumulator.accumulate(0.1), Some(ScrollDirection::Down));
+    }
+}
@@ -0,0 +1,56 @@
+//! User-configured shell commands triggered from the bar
+
+use std::process::Command;
+
+/// Substitute `{id}` and `{name}` in a command template.
+/// The name is shell-quoted so desktop names cannot inject commands.
+pub fn expand_command_template(template: &str, id: u32, name: &str) -> String {
+    template
+        .replace("{id}", &id.to_string())
+        .replace("{name}", &shell_quote(name))
+}
+
+/// Quote a value for safe use as a single `sh` word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Run a command through `sh -c` without blocking the GTK main loop
+pub fn spawn_shell_command(command: &str) {
+    log::debug!("Running shell command: {}", command);
+
+    match Command::new("sh").arg("-c").arg(command).spawn() {
+        Ok(mut child) => {
+            // Reap the child in the background so it does not linger as a zombie
+            std::thread::spawn(move || {
+                if let Err(e) = child.wait() {
+                    log::warn!("Failed to wait for shell command: {}", e);
+                }
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to run shell command '{}': {}", command, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_command_template() {
+        assert_eq!(
+            expand_command_template("notify-send {name} {id}", 2, "Research"),
+            "notify-send 'Research' 2"
+        );
+
+        // Names with shell metacharacters stay a single literal word
+        assert_eq!(
+            expand_command_template("echo {name}", 1, "a'; rm -rf ~; '"),
+            r"echo 'a'\''; rm -rf ~; '\'''"
+        );
+
+        assert_eq!(expand_command_template("hyprctl dispatch vdesk 1", 3, "x"), "hyprctl dispatch vdesk 1");
+    }
+}
@@ -1,20 +1,58 @@
 //! GTK widget management for virtual desktop display
 
-use crate::config::{ModuleConfig, SortStrategy};
-use crate::hyprland::HyprlandIPC;
+use crate::commands::{CommandQueue, DesktopCommand};
+use crate::config::{ClickAction, ModuleConfig, SortStrategy, TransitionType};
+use crate::health::{HealthReport, HealthState};
 use crate::metrics::PerformanceMetrics;
+use crate::monitor::MonitorSupervisor;
 use crate::vdesk::VirtualDesktop;
 use crate::errors::Result;
+use crate::ui::scroll::{neighbour_vdesk, ScrollDirection};
+use crate::ui::shell::{expand_command_template, spawn_shell_command};
 
-use std::collections::{BTreeMap, HashMap};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
 use std::sync::Arc;
-use waybar_cffi::gtk::{self, gdk, prelude::*, Button, Box as GtkBox};
+use std::time::Duration;
+use waybar_cffi::gtk::{self, gdk, glib, prelude::*, Button, Box as GtkBox, Revealer, RevealerTransitionType};
+
+/// Click actions for every desktop button, replaced when the configuration is reloaded
+#[derive(Debug, Clone)]
+pub struct ClickActions {
+    pub on_click: ClickAction,
+    pub on_click_middle: ClickAction,
+    pub on_click_right: ClickAction,
+}
+
+impl ClickActions {
+    pub fn from_config(config: &ModuleConfig) -> Self {
+        Self {
+            on_click: config.vdesk_on_click.clone(),
+            on_click_middle: config.vdesk_on_click_middle.clone(),
+            on_click_right: config.vdesk_on_click_right.clone(),
+        }
+    }
+
+    /// Action bound to a mouse button
+    fn for_button(&self, button: u32) -> Option<&ClickAction> {
+        match button {
+            1 => Some(&self.on_click),
+            2 => Some(&self.on_click_middle),
+            3 => Some(&self.on_click_right),
+            _ => None,
+        }
+    }
+}
 
 /// Virtual desktop widget
 #[derive(Debug)]
 pub struct VirtualDesktopWidget {
+    pub revealer: Revealer,
     pub button: Button,
     pub vdesk_id: u32,
+    /// Shared with the click handler so actions always see the current name
+    pub name: Rc<RefCell<String>>,
     pub display_text: String,
     pub tooltip_text: String,
     pub focused: bool,
@@ -28,6 +66,8 @@ impl VirtualDesktopWidget {
         display_text: String,
         tooltip_text: String,
         config: &ModuleConfig,
+        click_actions: &Rc<RefCell<ClickActions>>,
+        commands: &CommandQueue,
     ) -> Self {
         // Create Button directly with label text
         let button = Button::with_label(&display_text);
@@ -54,31 +94,29 @@ impl VirtualDesktopWidget {
             false.into()
         });
 
-        // Set up click handler using simpler connect_clicked signal
+        // Set up click handler on button-press so all three mouse buttons are distinguishable
+        let name = Rc::new(RefCell::new(vdesk.name.clone()));
         let vdesk_id_for_click = vdesk.id;
-        button.connect_clicked(move |_| {
-            let vdesk_id = vdesk_id_for_click;
-
-            // Spawn a new thread to handle the async task
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Builder::new_current_thread()
-                    .enable_all()
-                    .build()
-                    .unwrap();
-                
-                rt.block_on(async move {
-                    match HyprlandIPC::new().await {
-                        Ok(ipc) => {
-                            if let Err(e) = ipc.switch_to_virtual_desktop(vdesk_id).await {
-                                log::error!("Failed to switch to virtual desktop {}: {}", vdesk_id, e);
-                            }
-                        }
-                        Err(e) => {
-                            log::error!("Failed to create Hyprland IPC for click handler: {}", e);
-                        }
-                    }
-                });
-            });
+        let name_for_click = Rc::clone(&name);
+        let click_actions = Rc::clone(click_actions);
+        let commands = commands.clone();
+        button.connect_button_press_event(move |button, event| {
+            // Ignore the extra events GTK emits for double and triple clicks
+            if event.event_type() != gdk::EventType::ButtonPress {
+                return glib::Propagation::Proceed;
+            }
+
+            let click_actions = click_actions.borrow();
+            let Some(action) = click_actions.for_button(event.button()) else {
+                return glib::Propagation::Proceed;
+            };
+
+            let name = name_for_click.borrow();
+            if run_click_action(button, action, vdesk_id_for_click, &name, &commands) {
+                glib::Propagation::Stop
+            } else {
+                glib::Propagation::Proceed
+            }
         });
 
         // Apply CSS classes directly to button's style context
@@ -91,21 +129,23 @@ impl VirtualDesktopWidget {
             style_context.add_class("vdesk-unfocused");
             log::debug!("Applied CSS class 'vdesk-unfocused' to button for vdesk {}", vdesk.id);
         }
+        apply_state_classes(&style_context, vdesk);
+
+        // Wrap the button in a revealer so appearing and disappearing can animate.
+        // It starts collapsed; WidgetManager reveals it once it is in the container.
+        let revealer = Revealer::new();
+        apply_transition(&revealer, config);
+        revealer.set_reveal_child(false);
+        revealer.add(&button);
+        revealer.show_all();
 
-        // Set initial visibility based on configuration using GTK's built-in visibility
-        let is_visible = config.show_empty || vdesk.populated || vdesk.focused;
-        log::debug!("VDesk {} visibility check: show_empty={}, populated={}, focused={} => visible={}", 
-                   vdesk.id, config.show_empty, vdesk.populated, vdesk.focused, is_visible);
-        button.set_visible(is_visible);
-        if !is_visible {
-            log::debug!("Set GTK visibility to false for vdesk {}", vdesk.id);
-        }
-        
         log::debug!("Created new VirtualDesktopWidget for vdesk {} with text '{}'", vdesk.id, display_text);
 
         Self {
+            revealer,
             button,
             vdesk_id: vdesk.id,
+            name,
             display_text,
             tooltip_text,
             focused: vdesk.focused,
@@ -123,6 +163,11 @@ impl VirtualDesktopWidget {
     ) -> bool {
         let mut updated = false;
 
+        // Keep the name seen by click actions current
+        if *self.name.borrow() != vdesk.name {
+            self.name.replace(vdesk.name.clone());
+        }
+
         // Update display text if changed
         if self.display_text != display_text {
             self.button.set_label(&display_text);
@@ -137,18 +182,17 @@ impl VirtualDesktopWidget {
             updated = true;
         }
 
-        // Determine old and new visibility states
-        let was_visible = config.show_empty || self.populated || self.focused;
+        // Compare with what is shown, since a reloaded config may change visibility on its own
+        let was_visible = self.revealer.reveals_child();
         let is_visible = config.show_empty || vdesk.populated || vdesk.focused;
 
         let style_context = self.button.style_context();
 
-        // Toggle GTK visibility if needed
+        // Animate visibility change if needed
         if was_visible != is_visible {
             log::debug!("VDesk {} visibility changed: was_visible={} => is_visible={}", 
                        vdesk.id, was_visible, is_visible);
-            self.button.set_visible(is_visible);
-            log::debug!("Set GTK visibility to {} for vdesk {}", is_visible, vdesk.id);
+            self.revealer.set_reveal_child(is_visible);
             updated = true;
         }
         
@@ -163,6 +207,7 @@ impl VirtualDesktopWidget {
             }
             updated = true;
         }
+        apply_state_classes(&style_context, vdesk);
         
         // Update internal state for the next cycle
         self.focused = vdesk.focused;
@@ -178,23 +223,69 @@ pub struct WidgetManager {
     widgets: BTreeMap<u32, VirtualDesktopWidget>,
     widget_order: Vec<u32>,
     config: ModuleConfig,
+    click_actions: Rc<RefCell<ClickActions>>,
+    /// Desktops from the last update, re-rendered when the configuration changes
+    last_vdesks: Vec<VirtualDesktop>,
     metrics: Arc<PerformanceMetrics>,
+    commands: CommandQueue,
+    /// Shown instead of silence while Hyprland is unreachable or failing
+    placeholder: Button,
+    /// Shown while any setting had to fall back to its default
+    config_error: Button,
+    /// Connection health, whose tooltip shares the container with command errors
+    health: Cell<HealthState>,
+    /// Message of the last failed command, until a command succeeds
+    command_error: RefCell<Option<String>>,
 }
 
 impl WidgetManager {
     /// Create widget manager
-    pub fn new(container: GtkBox, config: ModuleConfig, metrics: Arc<PerformanceMetrics>) -> Self {
+    pub fn new(
+        container: GtkBox,
+        config: ModuleConfig,
+        metrics: Arc<PerformanceMetrics>,
+        commands: CommandQueue,
+        monitor: Arc<MonitorSupervisor>,
+    ) -> Self {
+        let placeholder = Button::new();
+        placeholder.set_relief(gtk::ReliefStyle::None);
+        placeholder.style_context().add_class("placeholder");
+        // Clicking skips the remaining backoff and reconnects right away, restarting
+        // the monitor if it has given up
+        placeholder.connect_clicked(move |_| {
+            log::info!("Placeholder clicked, requesting reconnect");
+            monitor.reconnect();
+        });
+        // Stays last so desktop positions match container indices; hidden until needed
+        placeholder.set_no_show_all(true);
+        container.add(&placeholder);
+
+        let config_error = Button::with_label(&config.format_error);
+        config_error.set_relief(gtk::ReliefStyle::None);
+        config_error.style_context().add_class("config-error");
+        config_error.set_no_show_all(true);
+        container.add(&config_error);
+
         Self {
             container,
             widgets: BTreeMap::new(),
             widget_order: Vec::new(),
+            click_actions: Rc::new(RefCell::new(ClickActions::from_config(&config))),
             config,
+            last_vdesks: Vec::new(),
             metrics,
+            commands,
+            placeholder,
+            config_error,
+            health: Cell::new(HealthState::default()),
+            command_error: RefCell::new(None),
         }
     }
 
     /// Update widgets with full desktop list - handles sorting and visibility internally
     pub fn update_widgets(&mut self, all_vdesks: &[VirtualDesktop]) -> Result<()> {
+        self.last_vdesks = all_vdesks.to_vec();
+
         // 1. Create a mutable copy to sort
         let mut sorted_vdesks = all_vdesks.to_vec();
 
@@ -218,13 +309,13 @@ impl WidgetManager {
         // 3. Generate the new widget order from the sorted list
         let new_order: Vec<u32> = sorted_vdesks.iter().map(|vd| vd.id).collect();
 
-        // 4. Iterate through all desktops to update or create widgets
+        // 4. Destroy widgets for desktops that no longer exist
+        let incoming_ids: HashSet<u32> = new_order.iter().copied().collect();
+        self.remove_stale_widgets(&incoming_ids);
+
+        // 5. Iterate through all desktops to update or create widgets
         for vdesk in &sorted_vdesks {
-            let display_text = self.config.format_virtual_desktop(
-                &vdesk.name,
-                vdesk.id,
-                vdesk.window_count,
-            );
+            let display_text = self.config.format_virtual_desktop(vdesk);
 
             let tooltip_text = self.config.format_tooltip(
                 &vdesk.name,
@@ -238,24 +329,35 @@ impl WidgetManager {
                 existing_widget.update_if_changed(vdesk, display_text, tooltip_text, &self.config);
             } else {
                 // Widget does not exist, create it (this will happen on first launch)
-                let widget = VirtualDesktopWidget::new(vdesk, display_text, tooltip_text, &self.config);
+                let widget = VirtualDesktopWidget::new(
+                    vdesk,
+                    display_text,
+                    tooltip_text,
+                    &self.config,
+                    &self.click_actions,
+                    &self.commands,
+                );
                 
-                // Add and position the new widget correctly
-                let correct_position = new_order.iter().position(|&id| id == vdesk.id).unwrap_or(0) as i32;
-                self.container.add(&widget.button);
-                self.container.reorder_child(&widget.button, correct_position);
+                // Add it ahead of the placeholder; step 6 moves it into its slot
+                let placeholder_position = self
+                    .container
+                    .children()
+                    .iter()
+                    .position(|child| child == self.placeholder.upcast_ref::<gtk::Widget>())
+                    .unwrap_or(0) as i32;
+                self.container.add(&widget.revealer);
+                self.container.reorder_child(&widget.revealer, placeholder_position);
                 
-                // Only show the widget if it should be visible according to configuration
+                // Only reveal the widget if it should be visible according to configuration.
+                // Revealing after it is in the (mapped) container animates the creation.
                 let is_visible = self.config.show_empty || vdesk.populated || vdesk.focused;
-                if is_visible {
-                    widget.button.show();
-                }
+                widget.revealer.set_reveal_child(is_visible);
                 
                 self.widgets.insert(vdesk.id, widget);
             }
         }
 
-        // 5. Physically reorder the GTK widgets if the sorted order has changed
+        // 6. Physically reorder the GTK widgets if the sorted order has changed
         if new_order != self.widget_order {
             self.optimize_widget_reordering(new_order)?;
         }
@@ -285,13 +387,163 @@ impl WidgetManager {
         self.container.show();
     }
 
+    /// Handle a scroll step: run the configured override command or switch to the neighbouring desktop
+    pub fn handle_scroll(&self, direction: ScrollDirection) {
+        let override_command = match direction {
+            ScrollDirection::Up => self.config.vdesk_on_scroll_up.as_deref(),
+            ScrollDirection::Down => self.config.vdesk_on_scroll_down.as_deref(),
+        };
+        if let Some(command) = override_command {
+            spawn_shell_command(command);
+            return;
+        }
+
+        match self.neighbour(direction, self.config.scroll_skip_empty) {
+            Some(vdesk_id) => {
+                log::debug!("Scroll {:?}: switching to vdesk {}", direction, vdesk_id);
+                self.commands.submit(DesktopCommand::Switch(vdesk_id));
+            }
+            None => log::debug!("Scroll {:?}: no neighbouring desktop to switch to", direction),
+        }
+    }
+
+    /// Desktop next to the focused one in bar order, wrapping according to `scroll_wrap`
+    ///
+    /// Scrolling up moves backwards, scrolling down forwards.
+    pub fn neighbour(&self, direction: ScrollDirection, skip_empty: bool) -> Option<u32> {
+        // Focused-first order changes on every switch, so move by desktop number instead
+        let order: Vec<u32> = match self.config.sort_by {
+            SortStrategy::FocusedFirst => self.widgets.keys().copied().collect(),
+            _ => self.widget_order.clone(),
+        };
+
+        neighbour_vdesk(&order, self.focused_vdesk(), direction, self.config.scroll_wrap, |id| {
+            !skip_empty || self.widgets.get(&id).is_some_and(|w| w.populated)
+        })
+    }
+
+    /// Currently focused desktop
+    pub fn focused_vdesk(&self) -> Option<u32> {
+        self.widgets.values().find(|w| w.focused).map(|w| w.vdesk_id)
+    }
+
+    /// Put the module into a visible error state after a failed command
+    pub fn show_command_error(&self, message: &str) {
+        let style_context = self.container.style_context();
+        style_context.add_class("error");
+        self.command_error.replace(Some(message.to_string()));
+        self.update_container_tooltip();
+    }
+
+    /// Leave the error state once a command succeeds again
+    pub fn clear_command_error(&self) {
+        let style_context = self.container.style_context();
+        if style_context.has_class("error") {
+            style_context.remove_class("error");
+            self.command_error.replace(None);
+            self.update_container_tooltip();
+        }
+    }
+
+    fn update_container_tooltip(&self) {
+        let tooltip = container_tooltip(self.health.get(), self.command_error.borrow().as_deref());
+        self.container.set_tooltip_text(tooltip.as_deref());
+    }
+
+    /// Show the monitor's connection health on the container and, when needed, the placeholder
+    pub fn set_health(&self, report: &HealthReport) {
+        let state = report.state;
+        let style_context = self.container.style_context();
+        for other in HealthState::ALL {
+            style_context.remove_class(other.css_class());
+        }
+        style_context.add_class(state.css_class());
+        self.health.set(state);
+        self.update_container_tooltip();
+
+        let placeholder_context = self.placeholder.style_context();
+        placeholder_context.remove_class("disconnected");
+        placeholder_context.remove_class("error");
+
+        match placeholder_kind(report) {
+            Some(kind) => {
+                let (label, class) = match kind {
+                    PlaceholderKind::Disconnected => (&self.config.format_disconnected, "disconnected"),
+                    PlaceholderKind::Error => (&self.config.format_error, "error"),
+                };
+                self.placeholder.set_label(label);
+                placeholder_context.add_class(class);
+                self.placeholder.set_tooltip_text(placeholder_tooltip(report).as_deref());
+                self.placeholder.show();
+            }
+            None => self.placeholder.hide(),
+        }
+    }
+
+    /// List configuration problems in the bar, or hide the indicator when there are none
+    pub fn set_config_errors(&self, messages: &[String]) {
+        if messages.is_empty() {
+            self.config_error.hide();
+            return;
+        }
+        self.config_error.set_label(&self.config.format_error);
+        self.config_error.set_tooltip_text(Some(&config_errors_tooltip(messages)));
+        self.config_error.show();
+    }
+
     /// Get configuration reference
     pub fn config(&self) -> &ModuleConfig {
         &self.config
     }
 
+    /// Apply a reloaded configuration, re-rendering the existing desktop buttons in place
+    pub fn set_config(&mut self, config: ModuleConfig) -> Result<()> {
+        self.click_actions.replace(ClickActions::from_config(&config));
+        for widget in self.widgets.values() {
+            apply_transition(&widget.revealer, &config);
+        }
+        self.config = config;
+
+        let vdesks = std::mem::take(&mut self.last_vdesks);
+        self.update_widgets(&vdesks)
+    }
+
+    /// Remove widgets whose virtual desktop disappeared (e.g. after `vdeskreset`)
+    fn remove_stale_widgets(&mut self, incoming_ids: &HashSet<u32>) {
+        for vdesk_id in find_stale_ids(&self.widgets, incoming_ids) {
+            if let Some(widget) = self.widgets.remove(&vdesk_id) {
+                log::debug!("Removing widget for vdesk {} which no longer exists", vdesk_id);
+                self.animate_removal(widget);
+                self.metrics.record_widget_removed();
+            }
+        }
+
+        // Keep the tracked order in sync with the container's children
+        self.widget_order.retain(|id| incoming_ids.contains(id));
+    }
+
+    /// Hide a widget with the configured transition, then remove it from the container.
+    /// Dropping the last reference after removal destroys the button.
+    fn animate_removal(&self, widget: VirtualDesktopWidget) {
+        let duration_ms = self.config.effective_transition_duration_ms();
+        if !widget.revealer.reveals_child() || duration_ms == 0 {
+            self.container.remove(&widget.revealer);
+            return;
+        }
+
+        widget.revealer.set_reveal_child(false);
+        let container = self.container.clone();
+        let revealer = widget.revealer;
+        glib::timeout_add_local_once(Duration::from_millis(duration_ms.into()), move || {
+            container.remove(&revealer);
+        });
+    }
+
     /// Optimized widget reordering with O(k) complexity where k = number of changed positions
     /// This minimizes GTK reorder operations and reduces visual flicker
+    ///
+    /// Positions come from the container's actual children, since revealers of removed
+    /// desktops stay in it until their transition ends.
     fn optimize_widget_reordering(&mut self, new_order: Vec<u32>) -> Result<()> {
         if new_order == self.widget_order {
             // Record that reordering was optimized (no moves needed)
@@ -299,41 +551,27 @@ impl WidgetManager {
             return Ok(());
         }
 
-        // Build position maps for efficient lookup
-        let current_positions: HashMap<u32, usize> = self.widget_order
-            .iter()
-            .enumerate()
-            .map(|(pos, &id)| (id, pos))
-            .collect();
-
-        let target_positions: HashMap<u32, usize> = new_order
+        let children = self.container.children();
+        let current: Vec<Option<u32>> = children
             .iter()
-            .enumerate()
-            .map(|(pos, &id)| (id, pos))
+            .map(|child| {
+                self.widgets
+                    .values()
+                    .find(|widget| widget.revealer.upcast_ref::<gtk::Widget>() == child)
+                    .map(|widget| widget.vdesk_id)
+            })
             .collect();
 
-        // Collect widgets that need to be moved
-        let mut moves_needed = Vec::new();
-        for (widget_id, &target_pos) in &target_positions {
-            if let Some(&current_pos) = current_positions.get(widget_id) {
-                if current_pos != target_pos {
-                    moves_needed.push((*widget_id, target_pos));
-                }
-            }
-        }
+        let moves = reorder_moves(&current, &new_order);
 
         // Only perform GTK operations if moves are actually needed
-        let was_optimized = moves_needed.len() < new_order.len();
-        if !moves_needed.is_empty() {
+        let was_optimized = moves.len() < new_order.len();
+        if !moves.is_empty() {
             log::debug!("Optimized reordering: moving {} out of {} widgets",
-                       moves_needed.len(), new_order.len());
+                       moves.len(), new_order.len());
 
-            // Batch GTK operations to reduce layout thrashing
-            // Note: GTK3 doesn't have freeze/thaw, but we can minimize calls
-            for (widget_id, target_pos) in moves_needed {
-                if let Some(widget) = self.widgets.get(&widget_id) {
-                    self.container.reorder_child(&widget.button, target_pos as i32);
-                }
+            for (child, position) in moves {
+                self.container.reorder_child(&children[child], position as i32);
             }
         } else {
             log::debug!("Optimized reordering: no moves needed");
@@ -347,6 +585,182 @@ impl WidgetManager {
     }
 }
 
+/// Moves that put the desktop buttons in `new_order` while every other child keeps its index
+///
+/// `current` lists the container's children, with `None` for anything that is not a live
+/// desktop button: revealers still animating out, the placeholder and the config error
+/// indicator. Each move is `(index in current, new index)`, applied in order with
+/// `reorder_child`.
+fn reorder_moves(current: &[Option<u32>], new_order: &[u32]) -> Vec<(usize, usize)> {
+    let index_of: HashMap<u32, usize> = current
+        .iter()
+        .enumerate()
+        .filter_map(|(index, id)| id.map(|id| (id, index)))
+        .collect();
+
+    // Desktop slots take the desktops in their new order; other children stay put
+    let mut ordered = new_order.iter().filter_map(|id| index_of.get(id).copied());
+    let target: Vec<usize> = current
+        .iter()
+        .enumerate()
+        .map(|(index, id)| match id {
+            Some(_) => ordered.next().unwrap_or(index),
+            None => index,
+        })
+        .collect();
+
+    let mut layout: Vec<usize> = (0..current.len()).collect();
+    let mut moves = Vec::new();
+    for (position, &child) in target.iter().enumerate() {
+        if layout[position] == child {
+            continue;
+        }
+        if let Some(from) = layout.iter().position(|&c| c == child) {
+            layout.remove(from);
+            layout.insert(position, child);
+            moves.push((child, position));
+        }
+    }
+    moves
+}
+
+/// Which placeholder, if any, represents a health report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlaceholderKind {
+    Disconnected,
+    Error,
+}
+
+fn placeholder_kind(report: &HealthReport) -> Option<PlaceholderKind> {
+    match report.state {
+        HealthState::Live => None,
+        HealthState::Disconnected | HealthState::Stopped => Some(PlaceholderKind::Disconnected),
+        // Not connected yet: only show something once an attempt has actually failed
+        HealthState::Connecting => report.error.as_ref().map(|_| PlaceholderKind::Disconnected),
+        HealthState::Degraded => Some(PlaceholderKind::Error),
+    }
+}
+
+/// Container tooltip: the health state, followed by the failed command if there is one
+fn container_tooltip(state: HealthState, command_error: Option<&str>) -> Option<String> {
+    match (state.tooltip(), command_error) {
+        (Some(health), Some(command)) => Some(format!("{}\n{}", health, command)),
+        (health, command) => health.or(command).map(str::to_string),
+    }
+}
+
+/// Placeholder tooltip: the underlying error, or what the module is doing about it
+fn placeholder_tooltip(report: &HealthReport) -> Option<String> {
+    match (report.state.tooltip(), report.error.as_deref()) {
+        // The error alone would read as if the module were still retrying
+        (Some(tooltip), Some(error)) if report.state == HealthState::Stopped => {
+            Some(format!("{}\n{}", tooltip, error))
+        }
+        (tooltip, error) => error.or(tooltip).map(str::to_string),
+    }
+}
+
+/// Tooltip listing every configuration problem
+fn config_errors_tooltip(messages: &[String]) -> String {
+    let mut tooltip = String::from("Invalid configuration, using defaults:");
+    for message in messages {
+        tooltip.push_str("\n• ");
+        tooltip.push_str(message);
+    }
+    tooltip
+}
+
+/// Run a click action for a desktop button, returning whether the click was handled
+fn run_click_action(
+    button: &Button,
+    action: &ClickAction,
+    vdesk_id: u32,
+    name: &str,
+    commands: &CommandQueue,
+) -> bool {
+    match action {
+        ClickAction::None => return false,
+        // By name, since IDs shift when the plugin's `names` are reordered
+        ClickAction::Switch => commands.submit(DesktopCommand::SwitchByName(name.to_string())),
+        ClickAction::MoveActiveWindowHere => commands.submit(DesktopCommand::MoveActiveWindow(vdesk_id)),
+        ClickAction::Rename => show_rename_popover(button, vdesk_id, name, commands.clone()),
+        ClickAction::Reset => commands.submit(DesktopCommand::Reset),
+        ClickAction::Command(template) => {
+            spawn_shell_command(&expand_command_template(template, vdesk_id, name));
+        }
+    }
+    true
+}
+
+/// Show an inline entry for renaming a virtual desktop
+fn show_rename_popover(
+    button: &Button,
+    vdesk_id: u32,
+    current_name: &str,
+    commands: CommandQueue,
+) {
+    let popover = gtk::Popover::new(Some(button));
+    let entry = gtk::Entry::new();
+    entry.set_text(current_name);
+    popover.add(&entry);
+
+    let popover_for_entry = popover.clone();
+    entry.connect_activate(move |entry| {
+        let new_name = entry.text().trim().to_string();
+        popover_for_entry.popdown();
+
+        if new_name.is_empty() {
+            return;
+        }
+        // The plugin's `names` option is a comma-separated list of `id:name` pairs
+        if new_name.contains(',') || new_name.contains(':') {
+            log::warn!("Cannot rename virtual desktop {} to '{}': names may not contain ',' or ':'",
+                       vdesk_id, new_name);
+            return;
+        }
+        commands.submit(DesktopCommand::Rename(vdesk_id, new_name));
+    });
+
+    popover.show_all();
+    popover.popup();
+}
+
+/// Toggle the classes for states that have their own format, so they can be styled too
+fn apply_state_classes(style_context: &gtk::StyleContext, vdesk: &VirtualDesktop) {
+    for (class, enabled) in [("vdesk-urgent", vdesk.urgent), ("vdesk-empty", !vdesk.populated)] {
+        if enabled {
+            style_context.add_class(class);
+        } else {
+            style_context.remove_class(class);
+        }
+    }
+}
+
+/// Set the configured animation on a desktop's revealer
+fn apply_transition(revealer: &Revealer, config: &ModuleConfig) {
+    revealer.set_transition_type(revealer_transition_type(config.transition_type));
+    revealer.set_transition_duration(config.effective_transition_duration_ms());
+}
+
+/// Map the configured transition to the GTK revealer animation
+fn revealer_transition_type(transition: TransitionType) -> RevealerTransitionType {
+    match transition {
+        TransitionType::None => RevealerTransitionType::None,
+        TransitionType::Crossfade => RevealerTransitionType::Crossfade,
+        TransitionType::SlideLeft => RevealerTransitionType::SlideLeft,
+        TransitionType::SlideRight => RevealerTransitionType::SlideRight,
+    }
+}
+
+/// IDs of tracked widgets that are missing from the incoming desktop list
+fn find_stale_ids<W>(widgets: &BTreeMap<u32, W>, incoming_ids: &HashSet<u32>) -> Vec<u32> {
+    widgets
+        .keys()
+        .filter(|id| !incoming_ids.contains(id))
+        .copied()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -362,6 +776,7 @@ mod tests {
             populated,
             window_count: if populated { 2 } else { 0 },
             workspaces: if populated { vec![id, id + 10] } else { vec![] },
+            urgent: false,
         }
     }
 
@@ -369,6 +784,7 @@ mod tests {
     #[ignore] // Requires GTK initialization
     fn test_widget_creation() {
         let config = ModuleConfig::default();
+        let (commands, _) = CommandQueue::new();
 
         let vdesk = create_test_vdesk(1, "Test Desktop", true, true);
         let widget = VirtualDesktopWidget::new(
@@ -376,6 +792,8 @@ mod tests {
             "Test Desktop".to_string(),
             "Virtual Desktop 1: Test Desktop".to_string(),
             &config,
+            &Rc::new(RefCell::new(ClickActions::from_config(&config))),
+            &commands,
         );
 
         assert_eq!(widget.vdesk_id, 1);
@@ -387,6 +805,7 @@ mod tests {
     #[ignore] // Requires GTK initialization
     fn test_widget_update_detection() {
         let config = ModuleConfig::default();
+        let (commands, _) = CommandQueue::new();
 
         let vdesk = create_test_vdesk(1, "Test Desktop", false, true);
         let mut widget = VirtualDesktopWidget::new(
@@ -394,6 +813,8 @@ mod tests {
             "Test Desktop".to_string(),
             "Tooltip".to_string(),
             &config,
+            &Rc::new(RefCell::new(ClickActions::from_config(&config))),
+            &commands,
         );
 
         // Test no change
@@ -445,7 +866,7 @@ mod tests {
         // Note: This would need GTK initialization in a real test environment
         // For now, we test the logic without actual GTK widgets
         
-        let vdesks = vec![
+        let vdesks = [
             create_test_vdesk(1, "Desktop 1", true, true),
             create_test_vdesk(2, "Desktop 2", false, true),
         ];
@@ -478,13 +899,141 @@ mod tests {
         assert_eq!(current_order, vec![3, 1, 2]);
     }
 
+    #[test]
+    fn test_placeholder_kind() {
+        assert_eq!(placeholder_kind(&HealthReport::new(HealthState::Live, None)), None);
+        assert_eq!(placeholder_kind(&HealthReport::new(HealthState::Connecting, None)), None);
+        assert_eq!(
+            placeholder_kind(&HealthReport::new(HealthState::Connecting, Some("socket not found".to_string()))),
+            Some(PlaceholderKind::Disconnected)
+        );
+        assert_eq!(
+            placeholder_kind(&HealthReport::new(HealthState::Disconnected, None)),
+            Some(PlaceholderKind::Disconnected)
+        );
+        assert_eq!(
+            placeholder_kind(&HealthReport::new(HealthState::Degraded, Some("plugin not loaded".to_string()))),
+            Some(PlaceholderKind::Error)
+        );
+        assert_eq!(
+            placeholder_kind(&HealthReport::new(HealthState::Stopped, None)),
+            Some(PlaceholderKind::Disconnected)
+        );
+    }
+
+    #[test]
+    fn test_container_tooltip_keeps_command_error() {
+        let error = "Hyprland rejected command 'dispatch vdesk 9': invalid dispatcher";
+        assert_eq!(container_tooltip(HealthState::Live, Some(error)).as_deref(), Some(error));
+        assert_eq!(container_tooltip(HealthState::Live, None), None);
+
+        let tooltip = container_tooltip(HealthState::Degraded, Some(error)).unwrap();
+        assert!(tooltip.starts_with(HealthState::Degraded.tooltip().unwrap()));
+        assert!(tooltip.ends_with(error));
+    }
+
+    #[test]
+    fn test_placeholder_tooltip_says_when_monitoring_stopped() {
+        let error = Some("socket not found".to_string());
+        assert_eq!(
+            placeholder_tooltip(&HealthReport::new(HealthState::Disconnected, error.clone())).as_deref(),
+            Some("socket not found")
+        );
+
+        let tooltip = placeholder_tooltip(&HealthReport::new(HealthState::Stopped, error)).unwrap();
+        assert!(tooltip.starts_with(HealthState::Stopped.tooltip().unwrap()));
+        assert!(tooltip.ends_with("socket not found"));
+    }
+
+    #[test]
+    fn test_click_actions_for_button() {
+        let config = ModuleConfig {
+            vdesk_on_click_right: ClickAction::Rename,
+            ..Default::default()
+        };
+        let actions = ClickActions::from_config(&config);
+
+        assert_eq!(actions.for_button(1), Some(&ClickAction::Switch));
+        assert_eq!(actions.for_button(2), Some(&ClickAction::None));
+        assert_eq!(actions.for_button(3), Some(&ClickAction::Rename));
+        assert_eq!(actions.for_button(8), None);
+    }
+
+    #[test]
+    fn test_config_errors_tooltip_lists_every_problem() {
+        let config = ModuleConfig {
+            format: "no placeholders".to_string(),
+            retry_max: 0,
+            ..Default::default()
+        };
+        let messages: Vec<String> = config.validation_errors().iter().map(|e| e.to_string()).collect();
+
+        let tooltip = config_errors_tooltip(&messages);
+        assert!(tooltip.starts_with("Invalid configuration"));
+        assert!(tooltip.contains("format"));
+        assert!(tooltip.contains("retry_max"));
+        assert_eq!(tooltip.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_find_stale_ids() {
+        let mut widgets: BTreeMap<u32, &str> = BTreeMap::new();
+        widgets.insert(1, "Widget 1");
+        widgets.insert(2, "Widget 2");
+        widgets.insert(3, "Widget 3");
+
+        // Nothing removed
+        let incoming: HashSet<u32> = [1, 2, 3].into_iter().collect();
+        assert!(find_stale_ids(&widgets, &incoming).is_empty());
+
+        // Desktop count lowered, e.g. after vdeskreset
+        let incoming: HashSet<u32> = [1].into_iter().collect();
+        assert_eq!(find_stale_ids(&widgets, &incoming), vec![2, 3]);
+
+        // New desktops are not reported as stale
+        let incoming: HashSet<u32> = [2, 4].into_iter().collect();
+        assert_eq!(find_stale_ids(&widgets, &incoming), vec![1, 3]);
+    }
+
+    /// Container order after applying `moves` the way `reorder_child` does
+    fn apply_moves(current: &[Option<u32>], moves: &[(usize, usize)]) -> Vec<Option<u32>> {
+        let mut layout: Vec<usize> = (0..current.len()).collect();
+        for &(child, position) in moves {
+            let from = layout.iter().position(|&c| c == child).unwrap();
+            layout.remove(from);
+            layout.insert(position, child);
+        }
+        layout.into_iter().map(|child| current[child]).collect()
+    }
+
+    #[test]
+    fn test_reorder_moves_leave_hiding_revealers_in_place() {
+        // Desktop 2 was removed in this burst and is still animating out, while the
+        // remaining desktops change order; the placeholder and config error stay last
+        let current = [Some(1), None, Some(3), Some(4), None, None];
+        let new_order = [4, 3, 1];
+
+        let moves = reorder_moves(&current, &new_order);
+        let layout = apply_moves(&current, &moves);
+        assert_eq!(layout, vec![Some(4), None, Some(3), Some(1), None, None]);
+    }
+
+    #[test]
+    fn test_reorder_moves_without_changes() {
+        let current = [Some(1), Some(2), None, Some(3), None];
+        assert!(reorder_moves(&current, &[1, 2, 3]).is_empty());
+
+        let moves = reorder_moves(&current, &[3, 2, 1]);
+        assert_eq!(apply_moves(&current, &moves), vec![Some(3), Some(2), None, Some(1), None]);
+    }
+
     #[test]
     fn test_optimized_reordering_logic() {
         use std::collections::HashMap;
 
         // Test position mapping logic
-        let current_order = vec![1, 2, 3, 4, 5];
-        let new_order = vec![1, 3, 2, 5, 4];
+        let current_order = [1, 2, 3, 4, 5];
+        let new_order = [1, 3, 2, 5, 4];
 
         let current_positions: HashMap<u32, usize> = current_order
             .iter()
@@ -518,7 +1067,7 @@ mod tests {
         use std::collections::HashMap;
 
         // Test no changes needed
-        let order = vec![1, 2, 3];
+        let order = [1, 2, 3];
         let current_positions: HashMap<u32, usize> = order
             .iter()
             .enumerate()
@@ -538,8 +1087,8 @@ mod tests {
         assert!(moves_needed.is_empty());
 
         // Test complete reversal
-        let current_order = vec![1, 2, 3];
-        let new_order = vec![3, 2, 1];
+        let current_order = [1, 2, 3];
+        let new_order = [3, 2, 1];
 
         let current_positions: HashMap<u32, usize> = current_order
             .iter()
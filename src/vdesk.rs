@@ -1,9 +1,11 @@
 //! Virtual desktop management and state tracking
 
 // src/vdesk.rs
-use crate::hyprland::HyprlandIPC;
+use crate::config::ModuleConfig;
+use crate::errors::VirtualDesktopError;
+use crate::hyprland::{is_connection_error, HyprlandIPC};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::Deserialize;
 
 #[derive(Debug, Clone, Deserialize)]
@@ -15,6 +17,9 @@ pub struct VirtualDesktop {
     #[serde(rename = "windows")]
     pub window_count: u32,
     pub workspaces: Vec<u32>,
+    /// A window on this desktop requested attention; tracked by the module, not the plugin
+    #[serde(default)]
+    pub urgent: bool,
 }
 
 impl VirtualDesktop {
@@ -26,52 +31,170 @@ impl VirtualDesktop {
             populated: false,
             window_count: 0,
             workspaces: Vec::new(),
+            urgent: false,
         }
     }
 }
 
+/// Window as listed by Hyprland's `clients` query, reduced to what urgency tracking needs
+#[derive(Debug, Deserialize)]
+struct Client {
+    address: String,
+    workspace: ClientWorkspace,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientWorkspace {
+    id: i64,
+}
+
+/// Window addresses appear with a `0x` prefix in queries but without one in events
+fn window_address(address: &str) -> &str {
+    address.trim().trim_start_matches("0x")
+}
+
 pub struct VirtualDesktopsManager {
     virtual_desktops: HashMap<u32, VirtualDesktop>,
     ipc: Option<HyprlandIPC>,
+    config: ModuleConfig,
+    /// Windows that requested attention and have not been seen yet
+    urgent_windows: HashSet<String>,
+}
+
+impl Default for VirtualDesktopsManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VirtualDesktopsManager {
     pub fn new() -> Self {
+        Self::with_config(&ModuleConfig::default())
+    }
+
+    /// Create manager using the IPC settings from the module configuration
+    pub fn with_config(config: &ModuleConfig) -> Self {
         Self {
             virtual_desktops: HashMap::new(),
             ipc: None,
+            config: config.clone(),
+            urgent_windows: HashSet::new(),
         }
     }
     
     pub async fn initialize(&mut self) -> Result<()> {
-        self.ipc = Some(HyprlandIPC::new().await?);
+        self.ipc = Some(self.connect().await?);
         self.update_state().await?;
         Ok(())
     }
     
     pub async fn update_state(&mut self) -> Result<()> {
         if self.ipc.is_none() {
-            self.ipc = Some(HyprlandIPC::new().await?);
+            self.ipc = Some(self.connect().await?);
         }
 
         let state = {
             let ipc = self.ipc.as_mut().unwrap();
-            ipc.get_virtual_desktop_state().await?
+            match ipc.get_virtual_desktop_state().await {
+                Ok(state) => state,
+                Err(e) => {
+                    // Hyprland may have restarted under a new signature, so rediscover next time
+                    if is_connection_error(&e) {
+                        self.ipc = None;
+                    }
+                    return Err(e);
+                }
+            }
         };
 
+        self.parse_virtual_desktop_state(&state)?;
+
+        if !self.urgent_windows.is_empty() {
+            // Urgency is cosmetic, so failing to place the windows must not fail the update
+            let ipc = self.ipc.as_ref().unwrap();
+            match ipc.get_clients().await {
+                Ok(clients) => {
+                    if let Err(e) = self.apply_urgency(&clients) {
+                        log::warn!("Failed to mark urgent desktops: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Failed to fetch windows for urgent desktops: {}", e),
+            }
+        }
 
+        Ok(())
+    }
 
-        self.parse_virtual_desktop_state(&state)?;
+    /// Remember a window that requested attention, from an `urgent>>ADDRESS` event
+    pub fn mark_urgent(&mut self, address: &str) {
+        self.urgent_windows.insert(window_address(address).to_string());
+    }
+
+    /// Flag desktops holding urgent windows, given the `j/clients` response
+    ///
+    /// A window stops being urgent once its desktop is focused or the window is gone.
+    fn apply_urgency(&mut self, clients: &str) -> Result<()> {
+        let clients: Vec<Client> = serde_json::from_str(clients)
+            .map_err(|e| anyhow::anyhow!("Failed to parse clients JSON: {}", e))?;
+        let workspaces: HashMap<&str, i64> = clients
+            .iter()
+            .map(|client| (window_address(&client.address), client.workspace.id))
+            .collect();
+
+        let vdesks = &mut self.virtual_desktops;
+        self.urgent_windows.retain(|address| {
+            let Some(&workspace) = workspaces.get(address.as_str()) else {
+                return false;
+            };
+            let Some(vdesk) = vdesks
+                .values_mut()
+                .find(|vd| vd.workspaces.iter().any(|&id| i64::from(id) == workspace))
+            else {
+                // Not on any desktop's workspace, e.g. a special workspace; keep it for later
+                return true;
+            };
+            if vdesk.focused {
+                return false;
+            }
+            vdesk.urgent = true;
+            true
+        });
 
         Ok(())
     }
     
+    /// Use new settings for future connections; the current connection is kept
+    pub fn set_config(&mut self, config: &ModuleConfig) {
+        self.config = config.clone();
+    }
+
+    /// Failing to find or reach an instance is reported as a connection error
+    async fn connect(&self) -> Result<HyprlandIPC> {
+        HyprlandIPC::with_config(&self.config).await.map_err(|e| {
+            VirtualDesktopError::IpcConnection {
+                source: std::io::Error::new(std::io::ErrorKind::ConnectionRefused, e.to_string()),
+            }
+            .into()
+        })
+    }
+
     pub fn get_virtual_desktops(&self) -> Vec<VirtualDesktop> {
         let mut vdesks: Vec<_> = self.virtual_desktops.values().cloned().collect();
         vdesks.sort_by_key(|vd| vd.id);
         vdesks
     }
     
+    /// Desktop a name refers to in the current state, see [`resolve_by_name`]
+    pub fn find_by_name(&self, query: &str) -> std::result::Result<VirtualDesktop, VirtualDesktopError> {
+        resolve_by_name(&self.get_virtual_desktops(), query).cloned()
+    }
+
+    /// Replace the state without asking Hyprland
+    #[cfg(test)]
+    pub(crate) fn set_virtual_desktops(&mut self, vdesks: Vec<VirtualDesktop>) {
+        self.virtual_desktops = vdesks.into_iter().map(|vd| (vd.id, vd)).collect();
+    }
+
     pub fn get_focused_virtual_desktop(&self) -> Option<&VirtualDesktop> {
         self.virtual_desktops.values().find(|vd| vd.focused)
     }
@@ -96,6 +219,42 @@ impl VirtualDesktopsManager {
 
 }
 
+/// Find the desktop a name refers to
+///
+/// An exact match wins, then a case-insensitive match, then a case-insensitive prefix.
+/// Several desktops matching at the same level make the name ambiguous.
+pub fn resolve_by_name<'a>(
+    vdesks: &'a [VirtualDesktop],
+    query: &str,
+) -> std::result::Result<&'a VirtualDesktop, VirtualDesktopError> {
+    let query = query.trim();
+    let lowercase = query.to_lowercase();
+    let levels: [&dyn Fn(&VirtualDesktop) -> bool; 3] = [
+        &|vd| vd.name == query,
+        &|vd| vd.name.to_lowercase() == lowercase,
+        &|vd| vd.name.to_lowercase().starts_with(&lowercase),
+    ];
+
+    for matches in levels {
+        let found: Vec<&VirtualDesktop> = vdesks.iter().filter(|vd| matches(vd)).collect();
+        match found.as_slice() {
+            [] => continue,
+            [vdesk] => return Ok(vdesk),
+            several => {
+                let candidates: Vec<String> = several.iter().map(|vd| format!("'{}'", vd.name)).collect();
+                return Err(VirtualDesktopError::AmbiguousDesktop {
+                    name: query.to_string(),
+                    candidates: candidates.join(", "),
+                });
+            }
+        }
+    }
+
+    Err(VirtualDesktopError::UnknownDesktop {
+        name: query.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,6 +304,63 @@ mod tests {
         assert!(research_vdesk.focused);
     }
 
+    #[test]
+    fn test_resolve_by_name() {
+        let vdesks = vec![
+            VirtualDesktop::new(1, "Focus".to_string()),
+            VirtualDesktop::new(2, "focus".to_string()),
+            VirtualDesktop::new(3, "Research".to_string()),
+            VirtualDesktop::new(4, "Reading".to_string()),
+            VirtualDesktop::new(5, "Comms".to_string()),
+        ];
+        let resolve = |query| resolve_by_name(&vdesks, query).map(|vd| vd.id);
+
+        // Exact matches take precedence over case-insensitive ones
+        assert_eq!(resolve("Focus").unwrap(), 1);
+        assert_eq!(resolve("focus").unwrap(), 2);
+        assert_eq!(resolve("research").unwrap(), 3);
+        assert_eq!(resolve(" COMMS ").unwrap(), 5);
+        assert_eq!(resolve("Resea").unwrap(), 3);
+
+        assert!(matches!(resolve("FOCUS"), Err(VirtualDesktopError::AmbiguousDesktop { .. })));
+        let err = resolve("re").unwrap_err();
+        assert!(err.to_string().contains("'Research', 'Reading'"));
+        assert!(matches!(resolve("Music"), Err(VirtualDesktopError::UnknownDesktop { .. })));
+    }
+
+    #[test]
+    fn test_apply_urgency() {
+        let mut manager = VirtualDesktopsManager::new();
+        manager.parse_virtual_desktop_state(r#"[
+            {"id": 1, "name": "Focus", "focused": true, "populated": true, "workspaces": [1, 2], "windows": 1},
+            {"id": 2, "name": "Comms", "focused": false, "populated": true, "workspaces": [3, 4], "windows": 2}
+        ]"#).unwrap();
+
+        manager.mark_urgent("80e62df0");
+        manager.mark_urgent("0x5a1b");
+        manager.mark_urgent("dead");
+        let clients = r#"[
+            {"address": "0x80e62df0", "workspace": {"id": 4, "name": "4"}, "class": "discord"},
+            {"address": "0x5a1b", "workspace": {"id": 2, "name": "2"}, "class": "kitty"}
+        ]"#;
+        manager.apply_urgency(clients).unwrap();
+
+        let vdesks = manager.get_virtual_desktops();
+        assert!(!vdesks[0].urgent);
+        assert!(vdesks[1].urgent);
+        // Only the window on the unfocused desktop is still urgent; the closed one is dropped
+        assert_eq!(manager.urgent_windows, HashSet::from(["80e62df0".to_string()]));
+
+        // Parsing a new state clears the flag until urgency is applied again
+        manager.parse_virtual_desktop_state(r#"[
+            {"id": 2, "name": "Comms", "focused": true, "populated": true, "workspaces": [3, 4], "windows": 2}
+        ]"#).unwrap();
+        assert!(!manager.get_virtual_desktops()[0].urgent);
+        manager.apply_urgency(clients).unwrap();
+        assert!(!manager.get_virtual_desktops()[0].urgent);
+        assert!(manager.urgent_windows.is_empty());
+    }
+
     #[test]
     fn test_parse_invalid_json() {
         let mut manager = VirtualDesktopsManager::new();